@@ -0,0 +1,418 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::game::Game;
+use crate::game::input::Action;
+use crate::render::hud::draw_hud;
+use crate::render::screens::{draw_banner, draw_menu, draw_text_screen, tint};
+use crate::render::{RendererData, ViewMode, available_threads, render_init};
+use crate::settings::{MAX_FOV, MIN_FOV, Settings, clamp_resolution};
+use crate::window_input::WindowInput;
+use minifb::{Key, KeyRepeat, Window};
+
+const PAUSED_TINT: (u32, f64) = (0x000000, 0.5);
+const GAME_OVER_TINT: (u32, f64) = (0x800000, 0.5);
+const LEVEL_COMPLETE_TINT: (u32, f64) = (0x208020, 0.4);
+const FOV_STEP: f64 = 5.0; // degrees the fov changes per key press
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum AppState {
+    Menu,          // pick a map from the map directory
+    Playing,       // the game runs
+    Paused,        // the game is frozen and drawn dimmed
+    GameOver,      // the player died
+    LevelComplete, // the player reached the exit of the map
+    Options, // shows the settings and reloads them; goes back to the pause screen if a game runs
+}
+
+struct MapEntry {
+    path: PathBuf,
+    name: String,
+    preview: Option<Game>, // None if the map failed to load
+}
+
+// the top level of the windowed game: which screen is shown, and what the keys do on it
+pub struct App {
+    pub state: AppState,
+    pub renderer_data: RendererData,
+    maps: Vec<MapEntry>,
+    selected: usize, // index into maps, also the map being played
+    game: Option<Game>,
+    input: WindowInput,
+    settings: Settings,
+    settings_path: PathBuf,
+    settings_message: Option<String>, // outcome of the last reload, shown on the options screen
+    window_size: (usize, usize),      // the frame follows the window when it is resized
+    quit: bool,
+}
+
+impl App {
+    // lists every .map file in map_directory; maps that fail to load stay in the list but can't be played;
+    // settings must already be validated, they were loaded from settings_path and are reloaded from there
+    pub fn new<P: AsRef<Path>>(
+        map_directory: P,
+        settings_path: PathBuf,
+        settings: Settings,
+    ) -> io::Result<Self> {
+        let mut paths: Vec<PathBuf> = fs::read_dir(map_directory)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|extension| extension == "map"))
+            .collect();
+        paths.sort();
+
+        let maps = paths
+            .into_iter()
+            .map(|path| {
+                let preview = Game::new(&path)
+                    .map_err(|e| eprintln!("failed to load map: {e}"))
+                    .ok();
+                let name = match &preview {
+                    Some(game) => game.map.metadata.name.clone(),
+                    None => path
+                        .file_stem()
+                        .map_or_else(String::new, |stem| stem.to_string_lossy().into_owned()),
+                };
+                MapEntry {
+                    path,
+                    name,
+                    preview,
+                }
+            })
+            .collect();
+
+        Ok(Self {
+            state: AppState::Menu,
+            renderer_data: render_init(
+                settings.resolution.0,
+                settings.resolution.1,
+                settings.fov.to_radians(),
+            ),
+            maps,
+            selected: 0,
+            game: None,
+            input: WindowInput::new(settings.triggers().expect("settings are validated")),
+            window_size: settings.resolution,
+            settings,
+            settings_path,
+            settings_message: None,
+            quit: false,
+        })
+    }
+
+    // false once the player chose to quit
+    pub fn update(&mut self, window: &mut Window, delta_time: f64) -> bool {
+        // a minimized window reports no size, the frame keeps its size until it comes back;
+        // a window smaller or larger than the settings allow shows the clamped frame stretched
+        let window_size = window.get_size();
+        if window_size != self.window_size && window_size.0 > 0 && window_size.1 > 0 {
+            self.window_size = window_size;
+            let fov = self.renderer_data.horizontal_fov;
+            set_view(&mut self.renderer_data, clamp_resolution(window_size), fov);
+        }
+
+        match self.state {
+            AppState::Menu => self.update_menu(window),
+            AppState::Playing => self.update_playing(window, delta_time),
+            AppState::Paused => {
+                if window.is_key_pressed(Key::Escape, KeyRepeat::No) {
+                    self.resume(window);
+                } else if window.is_key_pressed(Key::Q, KeyRepeat::No) {
+                    self.open_menu(window);
+                } else if window.is_key_pressed(Key::O, KeyRepeat::No) {
+                    self.open_options();
+                }
+            }
+            AppState::GameOver => {
+                if window.is_key_pressed(Key::Enter, KeyRepeat::No) {
+                    if !self.start(window, self.selected) {
+                        self.open_menu(window);
+                    }
+                } else if window.is_key_pressed(Key::Escape, KeyRepeat::No) {
+                    self.open_menu(window);
+                }
+            }
+            AppState::LevelComplete => {
+                if window.is_key_pressed(Key::Enter, KeyRepeat::No) {
+                    // maps that fail to load are skipped; after the last map it is back to the menu
+                    let next_maps = self.selected + 1..self.maps.len();
+                    if !next_maps.into_iter().any(|map| self.start(window, map)) {
+                        self.open_menu(window);
+                    }
+                } else if window.is_key_pressed(Key::Escape, KeyRepeat::No) {
+                    self.open_menu(window);
+                }
+            }
+            AppState::Options => {
+                if window.is_key_pressed(Key::R, KeyRepeat::No) {
+                    self.reload_settings(window);
+                } else if window.is_key_pressed(Key::Escape, KeyRepeat::No) {
+                    self.state = if self.game.is_some() {
+                        AppState::Paused
+                    } else {
+                        AppState::Menu
+                    };
+                }
+            }
+        }
+        !self.quit
+    }
+
+    fn update_menu(&mut self, window: &mut Window) {
+        if window.is_key_pressed(Key::Escape, KeyRepeat::No) {
+            self.quit = true;
+            return;
+        }
+        if window.is_key_pressed(Key::O, KeyRepeat::No) {
+            self.open_options();
+            return;
+        }
+        let count = self.maps.len();
+        if count == 0 {
+            return;
+        }
+        if window.is_key_pressed(Key::Up, KeyRepeat::Yes)
+            || window.is_key_pressed(Key::W, KeyRepeat::Yes)
+        {
+            self.selected = (self.selected + count - 1) % count;
+        }
+        if window.is_key_pressed(Key::Down, KeyRepeat::Yes)
+            || window.is_key_pressed(Key::S, KeyRepeat::Yes)
+        {
+            self.selected = (self.selected + 1) % count;
+        }
+        if window.is_key_pressed(Key::Enter, KeyRepeat::No) {
+            self.start(window, self.selected);
+        }
+    }
+
+    fn update_playing(&mut self, window: &mut Window, delta_time: f64) {
+        let Some(game) = &mut self.game else {
+            self.open_menu(window);
+            return;
+        };
+        if window.is_key_pressed(Key::Escape, KeyRepeat::No) {
+            self.state = AppState::Paused;
+            window.set_cursor_visibility(true);
+            return;
+        }
+
+        let input = self.input.read(window);
+        game.update(&input, delta_time);
+
+        //view toggles
+        let renderer_data = &mut self.renderer_data;
+        if window.is_key_pressed(Key::Tab, KeyRepeat::No) {
+            renderer_data.view_mode = match renderer_data.view_mode {
+                ViewMode::Camera => ViewMode::TopDown,
+                ViewMode::TopDown => ViewMode::Camera,
+            };
+        }
+        if window.is_key_pressed(Key::M, KeyRepeat::No) {
+            renderer_data.show_minimap = !renderer_data.show_minimap;
+        }
+        if window.is_key_pressed(Key::R, KeyRepeat::No) {
+            renderer_data.show_rays = !renderer_data.show_rays;
+        }
+        if window.is_key_pressed(Key::F3, KeyRepeat::No) {
+            renderer_data.show_debug = !renderer_data.show_debug;
+        }
+        for (key, step) in [(Key::LeftBracket, -FOV_STEP), (Key::RightBracket, FOV_STEP)] {
+            if window.is_key_pressed(key, KeyRepeat::Yes) {
+                let fov =
+                    (renderer_data.horizontal_fov.to_degrees() + step).clamp(MIN_FOV, MAX_FOV);
+                let size = (renderer_data.screen_width, renderer_data.screen_height);
+                set_view(renderer_data, size, fov.to_radians());
+            }
+        }
+        if window.is_key_pressed(Key::N, KeyRepeat::No) {
+            game.player.noclip = !game.player.noclip;
+        }
+        if window.is_key_pressed(Key::I, KeyRepeat::No) {
+            game.player.invert_y = !game.player.invert_y;
+        }
+        // single threaded rendering makes the renderer easier to debug and profile
+        if window.is_key_pressed(Key::T, KeyRepeat::No) {
+            renderer_data.threads = if renderer_data.threads == 1 {
+                available_threads()
+            } else {
+                1
+            };
+        }
+
+        if game.is_player_dead() {
+            self.state = AppState::GameOver;
+            window.set_cursor_visibility(true);
+        } else if game.is_level_complete() {
+            self.state = AppState::LevelComplete;
+            window.set_cursor_visibility(true);
+        }
+    }
+
+    // loads the map fresh, so a restarted level starts over with all enemies alive;
+    // false if it failed to load, the menu shows it as broken from then on
+    fn start(&mut self, window: &mut Window, map: usize) -> bool {
+        let Some(entry) = self.maps.get_mut(map) else {
+            return false;
+        };
+        match Game::new(&entry.path) {
+            Ok(mut game) => {
+                game.player.mouse_sensitivity = self.settings.mouse_sensitivity;
+                game.player.invert_y = self.settings.invert_y;
+                self.selected = map;
+                self.game = Some(game);
+                self.resume(window);
+                true
+            }
+            Err(e) => {
+                eprintln!("failed to load map: {e}");
+                entry.preview = None;
+                false
+            }
+        }
+    }
+
+    fn resume(&mut self, window: &mut Window) {
+        self.state = AppState::Playing;
+        window.set_cursor_visibility(false);
+        // the mouse moved freely while the game did not run, that must not turn the player
+        self.input.reset_mouse(window);
+    }
+
+    fn open_menu(&mut self, window: &mut Window) {
+        self.state = AppState::Menu;
+        self.game = None;
+        window.set_cursor_visibility(true);
+    }
+
+    fn open_options(&mut self) {
+        self.state = AppState::Options;
+        self.settings_message = None;
+    }
+
+    // keeps the current settings if the file is broken
+    fn reload_settings(&mut self, window: &mut Window) {
+        match Settings::load(&self.settings_path) {
+            Ok(settings) => {
+                let resolution_changed = settings.resolution != self.settings.resolution;
+                self.settings = settings;
+                self.apply_settings(window, resolution_changed);
+                self.settings_message = Some(String::from("settings reloaded"));
+            }
+            Err(e) => self.settings_message = Some(e.to_string()),
+        }
+    }
+
+    // hands the settings to everything that uses them; also called once after the window is created.
+    // the frame keeps following the window unless the resolution in the settings is a new one
+    pub fn apply_settings(&mut self, window: &mut Window, resolution_changed: bool) {
+        let settings = &self.settings;
+        self.input
+            .set_bindings(settings.triggers().expect("settings are validated"));
+        window.set_target_fps(settings.target_fps);
+        if let Some(game) = &mut self.game {
+            game.player.mouse_sensitivity = settings.mouse_sensitivity;
+            game.player.invert_y = settings.invert_y;
+        }
+        // the window keeps its size, it shows a new resolution stretched until it is resized
+        let resolution = if resolution_changed {
+            settings.resolution
+        } else {
+            clamp_resolution(self.window_size)
+        };
+        set_view(
+            &mut self.renderer_data,
+            resolution,
+            settings.fov.to_radians(),
+        );
+        self.renderer_data.render_scale = settings.render_scale;
+    }
+
+    fn options_lines(&self) -> Vec<String> {
+        let settings = &self.settings;
+        let mut lines = vec![
+            format!("MOUSE SENSITIVITY {}", settings.mouse_sensitivity),
+            format!("INVERT Y {}", settings.invert_y),
+            format!("FOV {}", settings.fov),
+            format!(
+                "RESOLUTION {}x{}",
+                settings.resolution.0, settings.resolution.1
+            ),
+            format!("RENDER SCALE {}", settings.render_scale),
+            format!("TARGET FPS {}", settings.target_fps),
+            String::new(),
+        ];
+        lines.extend(
+            Action::ALL.iter().map(|&action| {
+                format!("{action:?}: {}", settings.binding_names(action).join(", "))
+            }),
+        );
+        lines
+    }
+
+    // fps is only shown, the hud has no way to measure it; buffer has the size of renderer_data
+    pub fn draw(&self, buffer: &mut [u32], fps: f64) {
+        if self.state == AppState::Options {
+            let hint = format!("R: RELOAD {}  ESC: BACK", self.settings_path.display());
+            draw_text_screen(
+                buffer,
+                &self.renderer_data,
+                "OPTIONS",
+                &self.options_lines(),
+                self.settings_message.as_deref(),
+                &hint,
+            );
+            return;
+        }
+        let Some(game) = self.game.as_ref().filter(|_| self.state != AppState::Menu) else {
+            let entries: Vec<(&str, Option<&Game>)> = self
+                .maps
+                .iter()
+                .map(|entry| (entry.name.as_str(), entry.preview.as_ref()))
+                .collect();
+            draw_menu(buffer, &self.renderer_data, &entries, self.selected);
+            return;
+        };
+        crate::render::draw(buffer, &self.renderer_data, game);
+        draw_hud(buffer, &self.renderer_data, game, fps);
+        let overlay = match self.state {
+            AppState::Paused => Some((PAUSED_TINT, "PAUSED", "ESC: RESUME  Q: MENU  O: OPTIONS")),
+            AppState::GameOver => Some((GAME_OVER_TINT, "YOU DIED", "ENTER: RETRY  ESC: MENU")),
+            AppState::LevelComplete => Some((
+                LEVEL_COMPLETE_TINT,
+                "LEVEL COMPLETE",
+                "ENTER: NEXT MAP  ESC: MENU",
+            )),
+            AppState::Menu | AppState::Playing | AppState::Options => None,
+        };
+        if let Some(((color, amount), title, hint)) = overlay {
+            tint(buffer, color, amount);
+            draw_banner(buffer, &self.renderer_data, title, hint);
+        }
+    }
+
+    pub fn title(&self) -> String {
+        match self.maps.get(self.selected) {
+            Some(entry) if self.state != AppState::Menu => format!("game | {}", entry.name),
+            Some(_) => String::from("game"),
+            None => String::from("game | no maps found"),
+        }
+    }
+}
+
+// the renderer is set up anew for the frame size and fov; what was toggled stays
+fn set_view(
+    renderer_data: &mut RendererData,
+    (width, height): (usize, usize),
+    horizontal_fov: f64,
+) {
+    *renderer_data = RendererData {
+        view_mode: renderer_data.view_mode,
+        show_minimap: renderer_data.show_minimap,
+        show_rays: renderer_data.show_rays,
+        show_debug: renderer_data.show_debug,
+        threads: renderer_data.threads,
+        render_scale: renderer_data.render_scale,
+        ..render_init(width, height, horizontal_fov)
+    };
+}
@@ -0,0 +1,103 @@
+use super::map::{Map, Point, ShapeType, Side, polygon_contains};
+use super::side_grid::SideRef;
+
+const PUSH_OUT_ITERATIONS: usize = 4; // corners can push the circle into a neighbouring side, so resolve a few times
+
+// moves a circle by `movement` and pushes it out of every side it ends up overlapping;
+// only the part of the movement going into a side is removed, so the circle slides along it.
+// blocks no higher than climb_height don't stop the circle, it steps onto them (or is above them already)
+pub fn move_with_collision(
+    map: &Map,
+    from: Point,
+    movement: Point,
+    radius: f64,
+    climb_height: f64,
+) -> Point {
+    if radius <= 0.0 {
+        return from + movement;
+    }
+
+    // split long movements into steps so we cannot tunnel through thin sides
+    let step_count = (movement.length() / (radius / 2.0)).ceil().max(1.0) as usize;
+    let step = movement * (1.0 / step_count as f64);
+
+    let mut position = from;
+    for _ in 0..step_count {
+        let previous = position;
+        position = position + step;
+        for _ in 0..PUSH_OUT_ITERATIONS {
+            let mut pushed = false;
+            for side_ref in nearby_sides(map, position, radius, climb_height) {
+                let side = map.shapes().side(side_ref);
+                if let Some(corrected) = push_out_of_side(position, previous, radius, side) {
+                    position = corrected;
+                    pushed = true;
+                }
+            }
+            if !pushed {
+                break;
+            }
+        }
+    }
+    position
+}
+
+// walls, and blocks and sector floors too high to climb, that are close enough to touch the circle
+fn nearby_sides(map: &Map, center: Point, radius: f64, climb_height: f64) -> Vec<SideRef> {
+    let reach = Point {
+        x: radius,
+        y: radius,
+    };
+    let mut sides = map.side_grid.sides_in_box(center - reach, center + reach);
+    sides.retain(|&side_ref| match side_ref.shape_type {
+        ShapeType::Wall => true,
+        ShapeType::Block => map.shapes().side(side_ref).height > climb_height,
+        ShapeType::Sector => map.sectors[side_ref.shape_index].surfaces.floor_height > climb_height,
+    });
+    sides
+}
+
+// whether the circle reaches into the polygon outlined by the sides
+pub fn circle_overlaps_shape(sides: &[Side], center: Point, radius: f64) -> bool {
+    polygon_contains(sides, center)
+        || sides
+            .iter()
+            .any(|side| (center - closest_point_on_side(center, side)).length() < radius)
+}
+
+// returns the corrected center if the circle overlaps the side, None otherwise
+fn push_out_of_side(center: Point, previous: Point, radius: f64, side: &Side) -> Option<Point> {
+    let closest = closest_point_on_side(center, side);
+    let away = center - closest;
+    let distance = away.length();
+    if distance >= radius {
+        return None;
+    }
+
+    let direction = if distance > f64::EPSILON {
+        away * (1.0 / distance)
+    } else {
+        // center lies exactly on the side: push back towards the side we came from
+        let along = side.point2 - side.point1;
+        let normal = Point {
+            x: -along.y,
+            y: along.x,
+        } * (1.0 / along.length());
+        if (previous - closest).dot(normal) < 0.0 {
+            normal * -1.0
+        } else {
+            normal
+        }
+    };
+    Some(closest + direction * radius)
+}
+
+fn closest_point_on_side(point: Point, side: &Side) -> Point {
+    let along = side.point2 - side.point1;
+    let length_squared = along.dot(along);
+    if length_squared <= f64::EPSILON {
+        return side.point1;
+    }
+    let proportion = ((point - side.point1).dot(along) / length_squared).clamp(0.0, 1.0);
+    side.point1 + along * proportion
+}
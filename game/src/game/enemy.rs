@@ -0,0 +1,143 @@
+use super::collision::move_with_collision;
+use super::map::{Map, Point, ShapeType, Sprite};
+use super::player::{Player, STEP_HEIGHT};
+
+const SIGHT_RANGE: f64 = 200.0; // enemies notice the player this far away, if nothing is in between
+pub(crate) const ATTACK_RANGE: f64 = 25.0;
+const CHASE_DISTANCE: f64 = 20.0; // chasing enemies stop this close to the player, well inside the attack range
+pub(crate) const ATTACK_INTERVAL: f64 = 1.0; // seconds between two attacks
+const EYE_HEIGHT: f64 = 8.0; // above the floor the enemy stands on, for line of sight
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum EnemyState {
+    Idle,   // stands still until it sees the player
+    Chase,  // walks towards the player
+    Attack, // close enough to hit, every ATTACK_INTERVAL seconds
+    Dead,
+}
+
+#[derive(Clone)]
+pub struct Enemy {
+    pub position: Point,
+    pub health: f64,
+    pub state: EnemyState,
+    pub speed: f64,  // world units per second
+    pub damage: f64, // per attack
+    pub radius: f64,
+    pub width: f64,
+    pub height: f64,
+    pub texture: usize,              // index into Map::textures
+    pub dead_texture: Option<usize>, // None makes dead enemies disappear
+    pub attack_cooldown: f64,        // seconds until the next attack can hit
+    pub feet_height: f64, // floor the enemy stands on; Map::new puts placed enemies onto what is below them
+}
+
+impl Enemy {
+    pub fn update(&mut self, map: &Map, player: &mut Player, delta_time: f64) {
+        // lifts carry the living and the dead
+        self.feet_height = self.floor_height(map);
+        if self.health <= 0.0 {
+            self.state = EnemyState::Dead;
+        }
+        if self.state == EnemyState::Dead {
+            return;
+        }
+
+        let player_position = Point {
+            x: player.position_x,
+            y: player.position_y,
+        };
+        let to_player = player_position - self.position;
+        let distance = to_player.length();
+        let sees_player = distance <= SIGHT_RANGE && self.can_see(map, player);
+
+        self.state = if !sees_player {
+            EnemyState::Idle
+        } else if distance <= ATTACK_RANGE {
+            EnemyState::Attack
+        } else {
+            EnemyState::Chase
+        };
+        self.attack_cooldown = (self.attack_cooldown - delta_time).max(0.0);
+
+        match self.state {
+            EnemyState::Chase => {
+                // stop in front of the player instead of walking into them
+                let walk = (self.speed * delta_time).min(distance - CHASE_DISTANCE);
+                self.position = move_with_collision(
+                    map,
+                    self.position,
+                    to_player * (walk / distance),
+                    self.radius,
+                    self.feet_height + STEP_HEIGHT,
+                );
+                self.feet_height = self.floor_height(map);
+            }
+            EnemyState::Attack if self.attack_cooldown <= 0.0 => {
+                player.health = (player.health - self.damage).max(0.0);
+                self.attack_cooldown = ATTACK_INTERVAL;
+            }
+            _ => {}
+        }
+    }
+
+    // the line between the eyes of the enemy and the player must not pass a wall, or a block above the line
+    pub fn can_see(&self, map: &Map, player: &Player) -> bool {
+        let player_position = Point {
+            x: player.position_x,
+            y: player.position_y,
+        };
+        let to_player = player_position - self.position;
+        let distance = to_player.length();
+        if distance <= f64::EPSILON {
+            return true;
+        }
+        let eye_height = self.floor_height(map) + EYE_HEIGHT;
+
+        let mut visible = true;
+        map.side_grid.cast_ray(
+            map.shapes(),
+            self.position,
+            to_player.y.atan2(to_player.x),
+            distance,
+            |side_ref, hit| {
+                let line_height =
+                    eye_height + (player.view_height - eye_height) * (hit.distance / distance);
+                visible = match side_ref.shape_type {
+                    ShapeType::Wall => false,
+                    ShapeType::Block => hit.side.height < line_height,
+                    ShapeType::Sector => true,
+                };
+                visible
+            },
+        );
+        visible
+    }
+
+    // like the player, enemies get onto ledges up to STEP_HEIGHT above their feet; higher blocks are walls to them
+    pub fn floor_height(&self, map: &Map) -> f64 {
+        map.floor_height_at(self.position, self.feet_height + STEP_HEIGHT)
+    }
+
+    // what the renderer draws for the enemy, None once it is dead and has no corpse image
+    pub fn sprite(&self, map: &Map) -> Option<Sprite> {
+        let (texture, height) = match self.state {
+            // the corpse keeps the width and takes its height from the proportions of its image
+            EnemyState::Dead => {
+                let texture = self.dead_texture?;
+                let image = map.textures.get(texture)?;
+                let height = self.width * image.height as f64 / image.width as f64;
+                (texture, height)
+            }
+            _ => (self.texture, self.height),
+        };
+        Some(Sprite {
+            position: self.position,
+            width: self.width,
+            height,
+            // sprites are placed above the sector floor, the enemy may stand on a block
+            elevation: self.floor_height(map) - map.surfaces_at(self.position).floor_height,
+            texture,
+        })
+    }
+}
@@ -1,21 +1,446 @@
-use super::map::Map;
+use std::path::Path;
+
+use super::enemy::{Enemy, EnemyState};
+use super::hitscan::{self, Hit, HitTarget, Shot};
+use super::input::{Action, InputState};
+use super::map::{Map, Point, ShapeType};
+use super::map_loader::MapError;
 use super::player::Player;
-use minifb::Window;
+use super::random::Random;
+
+const USE_REACH: f64 = 10.0; // how far past the player's radius the use key reaches
+const RANDOM_SEED: u64 = 0x5eed;
+
+// things that happened during the last update, for whatever wants to react to them (sounds, decals, the hud)
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum GameEvent {
+    Fired { weapon: &'static str },
+    Impact(Hit), // one for every pellet that hits something
+    EnemyKilled { enemy: usize },
+}
 
 pub struct Game {
     pub player: Player,
     pub map: Map,
+    pub enemies: Vec<Enemy>,
+    pub events: Vec<GameEvent>, // cleared at the start of every update
+    pub random: Random,
+    pub time: f64, // seconds the game has been running, animates the lights
 }
 
 impl Game {
-    pub fn new() -> Self {
+    pub fn new<P: AsRef<Path>>(map_path: P) -> Result<Self, MapError> {
+        Ok(Self::from_map(Map::load(map_path)?))
+    }
+
+    // the player starts where the map says, the enemies as they are placed in it
+    pub fn from_map(map: Map) -> Self {
         Self {
-            player: Player::new(),
-            map: Map::new().unwrap(), // TODO remove unwrap
+            player: Player::new(&map),
+            enemies: map.enemies.clone(),
+            map,
+            events: Vec::new(),
+            random: Random::new(RANDOM_SEED),
+            time: 0.0,
+        }
+    }
+
+    // delta_time is the time since the last update in seconds; all movement is scaled by it
+    pub fn update(&mut self, input: &InputState, delta_time: f64) {
+        self.events.clear();
+        self.time += delta_time;
+        self.player.update(input, &self.map, delta_time);
+        for (action, weapon) in [(Action::SelectWeapon1, 0), (Action::SelectWeapon2, 1)] {
+            if input.was_pressed(action) {
+                self.player.current_weapon = weapon;
+            }
+        }
+        for weapon in &mut self.player.weapons {
+            weapon.update(delta_time);
+        }
+        if input.is_down(Action::Fire) {
+            self.fire();
+        }
+        if input.was_pressed(Action::Use) {
+            self.use_mover();
+        }
+        // dead enemies lie flat, doors close over them
+        let mut actors = vec![(
+            Point {
+                x: self.player.position_x,
+                y: self.player.position_y,
+            },
+            self.player.radius,
+        )];
+        actors.extend(
+            self.enemies
+                .iter()
+                .filter(|enemy| enemy.state != EnemyState::Dead)
+                .map(|enemy| (enemy.position, enemy.radius)),
+        );
+        self.map.update_movers(delta_time, &actors);
+        for enemy in &mut self.enemies {
+            enemy.update(&self.map, &mut self.player, delta_time);
+        }
+    }
+
+    pub fn is_player_dead(&self) -> bool {
+        self.player.health <= 0.0
+    }
+
+    pub fn is_level_complete(&self) -> bool {
+        self.map.is_in_exit(Point {
+            x: self.player.position_x,
+            y: self.player.position_y,
+        })
+    }
+
+    // activates the door or lift the player looks at, if it is close enough
+    fn use_mover(&mut self) {
+        let player_position = Point {
+            x: self.player.position_x,
+            y: self.player.position_y,
+        };
+        let mut target = None;
+        self.map.side_grid.cast_ray(
+            self.map.shapes(),
+            player_position,
+            self.player.view_angle,
+            self.player.radius + USE_REACH,
+            |side_ref, _| match side_ref.shape_type {
+                ShapeType::Sector => true,
+                ShapeType::Wall => false,
+                ShapeType::Block => {
+                    target = Some(side_ref.shape_index);
+                    false
+                }
+            },
+        );
+        if let Some(mover) = target.and_then(|block| self.map.blocks[block].mover.as_mut()) {
+            mover.activate();
+        }
+    }
+
+    // casts one ray per pellet of the current weapon, each hitting the nearest side or enemy in its way
+    pub fn fire(&mut self) {
+        if !self.player.weapon_mut().try_fire() {
+            return;
+        }
+        let weapon = *self.player.weapon();
+        self.events.push(GameEvent::Fired {
+            weapon: weapon.name,
+        });
+
+        for _ in 0..weapon.pellets {
+            let shot = Shot {
+                origin: Point {
+                    x: self.player.position_x,
+                    y: self.player.position_y,
+                },
+                height: self.player.view_height,
+                angle: self.player.view_angle + self.random.range(-weapon.spread, weapon.spread),
+                pitch: self.player.pitch,
+                range: weapon.range,
+            };
+            let Some(hit) = hitscan::trace(&shot, &self.map, &self.enemies) else {
+                continue;
+            };
+            self.events.push(GameEvent::Impact(hit));
+            if let HitTarget::Enemy(index) = hit.target {
+                let enemy = &mut self.enemies[index];
+                enemy.health -= weapon.damage;
+                if enemy.health <= 0.0 && enemy.state != EnemyState::Dead {
+                    enemy.state = EnemyState::Dead;
+                    self.events.push(GameEvent::EnemyKilled { enemy: index });
+                }
+            }
         }
     }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::game::enemy::{ATTACK_INTERVAL, ATTACK_RANGE};
+    use crate::game::map::{MapContents, MapMetadata, Sector, Shape, Surfaces};
+    use crate::game::player::{MOVESPEED, ROTATIONSPEED, STEP_HEIGHT};
+    use crate::game::test_maps;
+
+    const ROOM_SIZE: f64 = 200.0;
+    const DELTA_TIME: f64 = 0.1;
+
+    // empty square room, the player starts in the middle looking along +x
+    fn room() -> Game {
+        room_with(MapContents::default())
+    }
+
+    fn room_with(contents: MapContents) -> Game {
+        let metadata = MapMetadata {
+            player_start: Point {
+                x: ROOM_SIZE / 2.0,
+                y: ROOM_SIZE / 2.0,
+            },
+            player_start_angle: 0.0,
+            ..MapMetadata::default()
+        };
+        Game::from_map(test_maps::room(ROOM_SIZE, metadata, contents))
+    }
+
+    fn holding(action: Action) -> InputState {
+        let mut input = InputState::default();
+        input.hold(action);
+        input
+    }
+
+    #[test]
+    fn move_forward_moves_by_speed_times_delta_time() {
+        let mut game = room();
+        game.update(&holding(Action::MoveForward), DELTA_TIME);
+        let expected_x = ROOM_SIZE / 2.0 + MOVESPEED * ROTATIONSPEED * DELTA_TIME;
+        assert!((game.player.position_x - expected_x).abs() < 1e-9);
+        assert!((game.player.position_y - ROOM_SIZE / 2.0).abs() < 1e-9);
+
+        // without input the player stays where they are
+        game.update(&InputState::default(), DELTA_TIME);
+        assert!((game.player.position_x - expected_x).abs() < 1e-9);
+    }
+
+    #[test]
+    fn walls_stop_the_player_and_let_them_slide() {
+        let mut game = room();
+        let wall_x = ROOM_SIZE - game.player.radius;
+        let input = holding(Action::MoveForward);
+        for _ in 0..20 {
+            game.update(&input, DELTA_TIME);
+        }
+        assert!(game.player.position_x <= wall_x + 1e-9);
+        assert!(game.player.position_x > wall_x - 1.0);
+
+        // running into the wall at an angle only keeps the part of the movement along it
+        let start_y = game.player.position_y;
+        game.player.view_angle = 30f64.to_radians();
+        game.player.velocity_x = game.player.view_angle.cos() * ROTATIONSPEED;
+        game.player.velocity_y = game.player.view_angle.sin() * ROTATIONSPEED;
+        game.update(&input, DELTA_TIME);
+        assert!(game.player.position_x <= wall_x + 1e-9);
+        let along_wall = 30f64.to_radians().sin() * MOVESPEED * ROTATIONSPEED * DELTA_TIME;
+        assert!((game.player.position_y - start_y - along_wall).abs() < 1e-6);
+    }
+
+    // a block from x = 110 to 130 in front of the player, across the whole room
+    fn block_ahead(height: f64) -> Shape {
+        test_maps::rectangle(
+            Point { x: 110.0, y: 0.0 },
+            Point {
+                x: 130.0,
+                y: ROOM_SIZE,
+            },
+            ShapeType::Block,
+            height,
+        )
+    }
+
+    fn feet_height(game: &Game) -> f64 {
+        game.player.view_height - game.player.eye_height
+    }
+
+    fn walk_forward(game: &mut Game, seconds: f64) {
+        let input = holding(Action::MoveForward);
+        for _ in 0..(seconds / DELTA_TIME).round() as usize {
+            game.update(&input, DELTA_TIME);
+        }
+    }
+
+    fn wait(game: &mut Game, seconds: f64) {
+        for _ in 0..(seconds / DELTA_TIME).round() as usize {
+            game.update(&InputState::default(), DELTA_TIME);
+        }
+    }
+
+    #[test]
+    fn jumps_land_back_on_the_floor() {
+        let mut game = room();
+        game.update(&holding(Action::Jump), DELTA_TIME);
+        assert!(!game.player.on_ground);
+        let mut highest = feet_height(&game);
+        for _ in 0..20 {
+            game.update(&InputState::default(), DELTA_TIME);
+            highest = highest.max(feet_height(&game));
+        }
+        assert!(highest > STEP_HEIGHT);
+        assert!(game.player.on_ground);
+        assert_eq!(feet_height(&game), 0.0);
+    }
+
+    #[test]
+    fn steps_onto_low_blocks_but_not_onto_high_ones() {
+        let mut game = room_with(MapContents {
+            blocks: vec![block_ahead(STEP_HEIGHT)],
+            ..MapContents::default()
+        });
+        walk_forward(&mut game, 0.3);
+        assert!(game.player.position_x > 115.0);
+        assert_eq!(feet_height(&game), STEP_HEIGHT);
+
+        let mut game = room_with(MapContents {
+            blocks: vec![block_ahead(STEP_HEIGHT + 1.0)],
+            ..MapContents::default()
+        });
+        walk_forward(&mut game, 0.3);
+        assert!(game.player.position_x <= 110.0 - game.player.radius + 1e-9);
+        assert_eq!(feet_height(&game), 0.0);
+    }
+
+    #[test]
+    fn sector_floors_too_high_to_step_onto_stop_the_player() {
+        let sector = |floor_height| Sector {
+            outline: test_maps::rectangle(
+                Point { x: 110.0, y: 0.0 },
+                Point {
+                    x: 130.0,
+                    y: ROOM_SIZE,
+                },
+                ShapeType::Sector,
+                25.0,
+            ),
+            surfaces: Surfaces {
+                floor_height,
+                ceiling_height: 25.0,
+                floor_color: 0,
+                ceiling_color: Some(0),
+                light: 0,
+            },
+        };
+        let mut game = room_with(MapContents {
+            sectors: vec![sector(STEP_HEIGHT)],
+            ..MapContents::default()
+        });
+        walk_forward(&mut game, 0.3);
+        assert!(game.player.position_x > 115.0);
+        assert_eq!(feet_height(&game), STEP_HEIGHT);
+
+        let mut game = room_with(MapContents {
+            sectors: vec![sector(STEP_HEIGHT + 1.0)],
+            ..MapContents::default()
+        });
+        walk_forward(&mut game, 0.3);
+        assert!(game.player.position_x <= 110.0 - game.player.radius + 1e-9);
+        assert_eq!(feet_height(&game), 0.0);
+    }
+
+    #[test]
+    fn walking_off_a_block_drops_to_the_floor() {
+        // the player starts on top of the block, however high it is
+        let mut game = room_with(MapContents {
+            blocks: vec![test_maps::rectangle(
+                Point { x: 90.0, y: 90.0 },
+                Point { x: 110.0, y: 110.0 },
+                ShapeType::Block,
+                10.0,
+            )],
+            ..MapContents::default()
+        });
+        assert_eq!(feet_height(&game), 10.0);
+        wait(&mut game, DELTA_TIME);
+        assert_eq!(feet_height(&game), 10.0);
+
+        walk_forward(&mut game, 0.2);
+        assert!(game.player.position_x > 110.0);
+        wait(&mut game, 1.0);
+        assert!(game.player.on_ground);
+        assert_eq!(feet_height(&game), 0.0);
+    }
+
+    #[test]
+    fn noclip_flies_through_blocks_without_gravity() {
+        let mut game = room_with(MapContents {
+            blocks: vec![block_ahead(STEP_HEIGHT + 1.0)],
+            ..MapContents::default()
+        });
+        game.player.noclip = true;
+        walk_forward(&mut game, 0.5);
+        assert!(game.player.position_x > 120.0);
+        let height = game.player.view_height;
+        game.update(&holding(Action::Jump), DELTA_TIME);
+        assert!(game.player.view_height > height);
+        let height = game.player.view_height;
+        wait(&mut game, 1.0);
+        assert_eq!(game.player.view_height, height);
+    }
+
+    fn room_with_enemy(enemy_x: f64, walls: Vec<Shape>, blocks: Vec<Shape>) -> Game {
+        room_with(MapContents {
+            walls,
+            blocks,
+            enemies: vec![test_maps::enemy(Point {
+                x: enemy_x,
+                y: ROOM_SIZE / 2.0,
+            })],
+            ..MapContents::default()
+        })
+    }
+
+    #[test]
+    fn enemies_behind_walls_stay_idle() {
+        let wall = Shape::from_points(
+            vec![Point { x: 125.0, y: 50.0 }, Point { x: 125.0, y: 150.0 }],
+            ShapeType::Wall,
+            25.0,
+        )
+        .unwrap();
+        let mut game = room_with_enemy(150.0, vec![wall], Vec::new());
+        assert!(!game.enemies[0].can_see(&game.map, &game.player));
+        wait(&mut game, 1.0);
+        assert_eq!(game.enemies[0].state, EnemyState::Idle);
+        assert_eq!(game.enemies[0].position.x, 150.0);
+    }
+
+    #[test]
+    fn enemies_in_sight_chase_the_player() {
+        let mut game = room_with_enemy(160.0, Vec::new(), Vec::new());
+        assert!(game.enemies[0].can_see(&game.map, &game.player));
+        wait(&mut game, DELTA_TIME);
+        let enemy = &game.enemies[0];
+        assert_eq!(enemy.state, EnemyState::Chase);
+        assert!((enemy.position.x - (160.0 - enemy.speed * DELTA_TIME)).abs() < 1e-9);
+        assert_eq!(game.player.health, 100.0);
+    }
+
+    #[test]
+    fn enemies_in_attack_range_hit_once_per_interval() {
+        let mut game = room_with_enemy(100.0 + ATTACK_RANGE - 1.0, Vec::new(), Vec::new());
+        let damage = game.enemies[0].damage;
+        let health = game.player.health;
+        // 0.25 seconds add up to the interval exactly
+        let delta_time = 0.25;
+        let steps = (ATTACK_INTERVAL / delta_time) as usize;
+        game.update(&InputState::default(), delta_time);
+        assert_eq!(game.enemies[0].state, EnemyState::Attack);
+        assert_eq!(game.player.health, health - damage);
+        for _ in 1..steps {
+            game.update(&InputState::default(), delta_time);
+        }
+        assert_eq!(game.player.health, health - damage);
+        game.update(&InputState::default(), delta_time);
+        assert_eq!(game.player.health, health - 2.0 * damage);
+
+        game.enemies[0].health = 0.0;
+        game.update(&InputState::default(), ATTACK_INTERVAL);
+        assert_eq!(game.enemies[0].state, EnemyState::Dead);
+        assert_eq!(game.player.health, health - 2.0 * damage);
+    }
 
-    pub fn update(&mut self, window: &Window) {
-        self.player.update(window, &self.map);
+    #[test]
+    fn enemies_on_blocks_are_drawn_on_them() {
+        let block = test_maps::rectangle(
+            Point { x: 150.0, y: 90.0 },
+            Point { x: 170.0, y: 110.0 },
+            ShapeType::Block,
+            STEP_HEIGHT,
+        );
+        let game = room_with_enemy(160.0, Vec::new(), vec![block]);
+        let enemy = &game.enemies[0];
+        assert_eq!(enemy.floor_height(&game.map), STEP_HEIGHT);
+        assert_eq!(enemy.sprite(&game.map).unwrap().elevation, STEP_HEIGHT);
     }
 }
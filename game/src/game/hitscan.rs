@@ -0,0 +1,221 @@
+use super::enemy::{Enemy, EnemyState};
+use super::map::{Map, Point, ShapeType};
+use super::side_grid::SideRef;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum HitTarget {
+    Side(SideRef),
+    Enemy(usize), // index into Game::enemies
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Hit {
+    pub target: HitTarget,
+    pub position: Point,
+    pub height: f64,
+    pub distance: f64,
+}
+
+// a shot leaving the eye at `height`, going along angle and climbing by tan(pitch) per unit of distance,
+// the same way the y-sheared camera view shows it
+pub struct Shot {
+    pub origin: Point,
+    pub height: f64,
+    pub angle: f64,
+    pub pitch: f64,
+    pub range: f64,
+}
+
+impl Shot {
+    fn height_at(&self, distance: f64) -> f64 {
+        self.height + self.pitch.tan() * distance
+    }
+
+    fn position_at(&self, distance: f64) -> Point {
+        self.origin
+            + Point {
+                x: self.angle.cos(),
+                y: self.angle.sin(),
+            } * distance
+    }
+}
+
+// nearest wall, block or living enemy the shot runs into
+pub fn trace(shot: &Shot, map: &Map, enemies: &[Enemy]) -> Option<Hit> {
+    let mut nearest: Option<Hit> = None;
+    map.side_grid.cast_ray(
+        map.shapes(),
+        shot.origin,
+        shot.angle,
+        shot.range,
+        |side_ref, hit| {
+            let height = shot.height_at(hit.distance);
+            let stops_shot = match side_ref.shape_type {
+                ShapeType::Wall => true,
+                ShapeType::Block => height <= hit.side.height,
+                ShapeType::Sector => false,
+            };
+            if stops_shot {
+                nearest = Some(Hit {
+                    target: HitTarget::Side(side_ref),
+                    position: hit.position,
+                    height,
+                    distance: hit.distance,
+                });
+            }
+            !stops_shot
+        },
+    );
+
+    let max_distance = nearest.map_or(shot.range, |hit| hit.distance);
+    for (index, enemy) in enemies.iter().enumerate() {
+        if enemy.state == EnemyState::Dead {
+            continue;
+        }
+        let Some(distance) = ray_circle_distance(shot, enemy.position, enemy.radius) else {
+            continue;
+        };
+        let height = shot.height_at(distance);
+        let floor = enemy.floor_height(map);
+        if distance < max_distance
+            && height >= floor
+            && height <= floor + enemy.height
+            && nearest.is_none_or(|hit| distance < hit.distance)
+        {
+            nearest = Some(Hit {
+                target: HitTarget::Enemy(index),
+                position: shot.position_at(distance),
+                height,
+                distance,
+            });
+        }
+    }
+    nearest
+}
+
+// distance along the ray to where it first enters the circle, None if it misses or the circle is behind
+fn ray_circle_distance(shot: &Shot, center: Point, radius: f64) -> Option<f64> {
+    let direction = Point {
+        x: shot.angle.cos(),
+        y: shot.angle.sin(),
+    };
+    let to_center = center - shot.origin;
+    let along = to_center.dot(direction);
+    let off_ray_squared = to_center.dot(to_center) - along * along;
+    if off_ray_squared > radius * radius {
+        return None;
+    }
+    let half_chord = (radius * radius - off_ray_squared).sqrt();
+    let distance = if along - half_chord >= 0.0 {
+        along - half_chord
+    } else {
+        along + half_chord // the shot starts inside the circle
+    };
+    (distance >= 0.0).then_some(distance)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::game::map::{MapContents, MapMetadata, Shape};
+    use crate::game::test_maps;
+
+    const WALL_X: f64 = 100.0;
+
+    // room from 0 to 200 split by a wall at WALL_X
+    fn map() -> Map {
+        let metadata = MapMetadata::default();
+        let split = Shape::from_points(
+            vec![
+                Point { x: WALL_X, y: 0.0 },
+                Point {
+                    x: WALL_X,
+                    y: 200.0,
+                },
+            ],
+            ShapeType::Wall,
+            metadata.level_height,
+        )
+        .unwrap();
+        let contents = MapContents {
+            walls: vec![split],
+            ..MapContents::default()
+        };
+        test_maps::room(200.0, metadata, contents)
+    }
+
+    fn enemy(x: f64) -> Enemy {
+        test_maps::enemy(Point { x, y: 50.0 })
+    }
+
+    // along +x at y = 50, at the height of the enemies' middle
+    fn shot(origin_x: f64) -> Shot {
+        Shot {
+            origin: Point {
+                x: origin_x,
+                y: 50.0,
+            },
+            height: 10.0,
+            angle: 0.0,
+            pitch: 0.0,
+            range: 1000.0,
+        }
+    }
+
+    #[test]
+    fn trace_takes_the_nearest_of_sides_and_enemies() {
+        let map = map();
+        let wall_distance = |hit: Option<Hit>| match hit {
+            Some(Hit {
+                target: HitTarget::Side(side_ref),
+                distance,
+                ..
+            }) => {
+                assert_eq!(side_ref.shape_index, 1);
+                distance
+            }
+            other => panic!("expected the wall, got {other:?}"),
+        };
+
+        // the enemy in front of the wall is hit where the shot enters its circle
+        let hit = trace(&shot(20.0), &map, &[enemy(120.0), enemy(80.0)]).unwrap();
+        assert_eq!(hit.target, HitTarget::Enemy(1));
+        assert_eq!(hit.distance, 55.0);
+        assert_eq!(hit.position, Point { x: 75.0, y: 50.0 });
+
+        // behind the wall, dead or shot over, enemies are not hit
+        assert_eq!(
+            wall_distance(trace(&shot(20.0), &map, &[enemy(120.0)])),
+            80.0
+        );
+        let mut dead = enemy(80.0);
+        dead.state = EnemyState::Dead;
+        assert_eq!(wall_distance(trace(&shot(20.0), &map, &[dead])), 80.0);
+        let mut over = shot(20.0);
+        over.height = 30.0;
+        assert_eq!(wall_distance(trace(&over, &map, &[enemy(80.0)])), 80.0);
+
+        // out of range nothing is hit
+        let mut short = shot(20.0);
+        short.range = 50.0;
+        assert_eq!(trace(&short, &map, &[enemy(80.0)]), None);
+    }
+
+    #[test]
+    fn shots_from_inside_an_enemy_hit_it_where_they_leave_it() {
+        let map = map();
+        let hit = trace(&shot(78.0), &map, &[enemy(80.0)]).unwrap();
+        assert_eq!(hit.target, HitTarget::Enemy(0));
+        assert_eq!(hit.distance, 7.0);
+
+        let center = Point { x: 80.0, y: 50.0 };
+        assert_eq!(ray_circle_distance(&shot(80.0), center, 5.0), Some(5.0));
+        assert_eq!(ray_circle_distance(&shot(84.0), center, 5.0), Some(1.0));
+        // behind the shot or beside it
+        assert_eq!(ray_circle_distance(&shot(90.0), center, 5.0), None);
+        assert_eq!(
+            ray_circle_distance(&shot(20.0), Point { x: 80.0, y: 60.0 }, 5.0),
+            None
+        );
+    }
+}
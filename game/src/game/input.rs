@@ -0,0 +1,68 @@
+use serde::{Deserialize, Serialize};
+
+// what the player wants to do this frame, independent of where it comes from (window, replay, test, ...)
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Serialize, Deserialize)]
+pub enum Action {
+    MoveForward,
+    MoveBackward,
+    StrafeLeft,
+    StrafeRight,
+    TurnLeft,
+    TurnRight,
+    LookUp,
+    LookDown,
+    Jump,    // also flies up in noclip
+    FlyDown, // only in noclip
+    Fire,
+    Use, // opens doors, starts lifts
+    SelectWeapon1,
+    SelectWeapon2,
+}
+
+impl Action {
+    pub const ALL: [Action; 14] = [
+        Action::MoveForward,
+        Action::MoveBackward,
+        Action::StrafeLeft,
+        Action::StrafeRight,
+        Action::TurnLeft,
+        Action::TurnRight,
+        Action::LookUp,
+        Action::LookDown,
+        Action::Jump,
+        Action::FlyDown,
+        Action::Fire,
+        Action::Use,
+        Action::SelectWeapon1,
+        Action::SelectWeapon2,
+    ];
+}
+
+// the input for one update; held actions stay set as long as their key is down,
+// pressed ones only in the frame the key went down
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct InputState {
+    held: [bool; Action::ALL.len()],
+    pressed: [bool; Action::ALL.len()],
+    pub mouse_delta: (f64, f64), // pixels the mouse moved since the last update, y grows downwards
+}
+
+impl InputState {
+    pub fn is_down(&self, action: Action) -> bool {
+        self.held[action as usize]
+    }
+
+    pub fn was_pressed(&self, action: Action) -> bool {
+        self.pressed[action as usize]
+    }
+
+    // pressing counts as holding as well, so a key tapped within one frame is not lost
+    pub fn press(&mut self, action: Action) {
+        self.pressed[action as usize] = true;
+        self.held[action as usize] = true;
+    }
+
+    pub fn hold(&mut self, action: Action) {
+        self.held[action as usize] = true;
+    }
+}
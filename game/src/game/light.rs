@@ -0,0 +1,143 @@
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+
+use super::random::Random;
+
+const STROBE_ON: f64 = 0.2; // part of each period a strobe is at its full level
+const FLICKER_BRIGHT_CHANCE: f64 = 0.6;
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum LightEffect {
+    Steady,
+    Flicker, // jumps between level and low_level at random, once per period
+    Pulse,   // fades from level down to low_level and back once per period
+    Strobe,  // flashes up to level for a short part of every period, low_level otherwise
+}
+
+// how brightly a region or side is lit; multiplies the shading by distance and angle
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Light {
+    pub level: f64, // 1 is fully lit, 0 is black
+    pub effect: LightEffect,
+    pub low_level: f64, // where the effect goes down to
+    pub period: f64,    // seconds
+    pub phase: f64,     // seconds the effect is ahead, so equal lights don't run in step
+}
+
+impl Default for Light {
+    fn default() -> Self {
+        Self {
+            level: 1.0,
+            effect: LightEffect::Steady,
+            low_level: 0.2,
+            period: 1.0,
+            phase: 0.0,
+        }
+    }
+}
+
+impl Light {
+    // the light level at time seconds into the game
+    pub fn level_at(&self, time: f64) -> f64 {
+        let cycles = (time + self.phase) / self.period;
+        match self.effect {
+            LightEffect::Steady => self.level,
+            LightEffect::Flicker => {
+                // every period draws anew, the same for every caller in that period; the multiplication
+                // spreads neighbouring periods over the seeds
+                let period = cycles.floor() as i64 as u64;
+                let mut random = Random::new(period.wrapping_mul(0x9e37_79b9_7f4a_7c15));
+                if random.next_f64() < FLICKER_BRIGHT_CHANCE {
+                    self.level
+                } else {
+                    self.low_level
+                }
+            }
+            LightEffect::Pulse => {
+                let fade = 0.5 + 0.5 * (2.0 * PI * cycles).cos();
+                self.low_level + (self.level - self.low_level) * fade
+            }
+            LightEffect::Strobe => {
+                if cycles.rem_euclid(1.0) < STROBE_ON {
+                    self.level
+                } else {
+                    self.low_level
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn light(effect: LightEffect) -> Light {
+        Light {
+            level: 0.9,
+            effect,
+            low_level: 0.3,
+            period: 2.0,
+            phase: 0.0,
+        }
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-9, "{actual} != {expected}");
+    }
+
+    #[test]
+    fn steady_keeps_its_level() {
+        for time in [0.0, 0.7, 123.4] {
+            assert_eq!(light(LightEffect::Steady).level_at(time), 0.9);
+        }
+    }
+
+    #[test]
+    fn pulse_fades_down_and_back_once_per_period() {
+        let pulse = light(LightEffect::Pulse);
+        assert_close(pulse.level_at(0.0), 0.9);
+        assert_close(pulse.level_at(0.5), 0.6);
+        assert_close(pulse.level_at(1.0), 0.3);
+        assert_close(pulse.level_at(2.0), 0.9);
+        assert_close(pulse.level_at(3.0), 0.3);
+
+        let ahead = Light {
+            phase: 1.0,
+            ..pulse
+        };
+        assert_close(ahead.level_at(0.0), 0.3);
+    }
+
+    #[test]
+    fn strobe_is_bright_at_the_start_of_every_period() {
+        let strobe = light(LightEffect::Strobe);
+        // bright for STROBE_ON of the 2 second period
+        for (time, level) in [
+            (0.0, 0.9),
+            (0.39, 0.9),
+            (0.41, 0.3),
+            (1.9, 0.3),
+            (2.1, 0.9),
+            (-1.9, 0.9),
+        ] {
+            assert_eq!(strobe.level_at(time), level, "at {time}");
+        }
+    }
+
+    #[test]
+    fn flicker_holds_each_draw_for_a_period() {
+        let flicker = light(LightEffect::Flicker);
+        let levels: Vec<f64> = (0..200).map(|i| flicker.level_at(i as f64 * 2.0)).collect();
+        assert!(levels.iter().all(|&level| level == 0.9 || level == 0.3));
+        // both levels come up, roughly as often as FLICKER_BRIGHT_CHANCE says
+        let bright = levels.iter().filter(|&&level| level == 0.9).count();
+        assert!((80..160).contains(&bright), "{bright} of 200 bright");
+
+        for i in 0..20 {
+            let start = i as f64 * 2.0;
+            assert_eq!(flicker.level_at(start + 0.1), flicker.level_at(start + 1.9));
+        }
+    }
+}
@@ -1,8 +1,16 @@
-use std::ops::{Add, Sub};
+use serde::{Deserialize, Serialize};
+use std::ops::{Add, Mul, Sub};
+use std::path::Path;
 
-pub const LEVEL_HEIGHT: f64 = 25.0; // TODO different for every map
+use super::collision::circle_overlaps_shape;
+use super::enemy::Enemy;
+use super::light::Light;
+use super::map_loader::{MapError, MapFile};
+use super::mover::{Mover, MoverState};
+use super::side_grid::{MapShapes, SideGrid};
+use crate::render::texture::Texture;
 
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
 pub struct Point {
     pub x: f64,
     pub y: f64,
@@ -28,11 +36,32 @@ impl Add for Point {
         }
     }
 }
+impl Mul<f64> for Point {
+    type Output = Self;
 
-#[derive(Clone, Copy, PartialEq)]
+    fn mul(self, factor: f64) -> Self::Output {
+        Self {
+            x: self.x * factor,
+            y: self.y * factor,
+        }
+    }
+}
+
+impl Point {
+    pub fn dot(self, other: Self) -> f64 {
+        self.x * other.x + self.y * other.y
+    }
+
+    pub fn length(self) -> f64 {
+        self.dot(self).sqrt()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
 pub enum ShapeType {
     Wall,
     Block,
+    Sector, // outline of a floor/ceiling region; its sides are never drawn
 }
 
 #[derive(Clone, PartialEq)]
@@ -42,17 +71,23 @@ pub struct Side {
     pub side_type: ShapeType,
     pub angle_in_world: f64,
     pub height: f64,
+    pub length: f64,
+    pub texture: Option<usize>, // index into Map::textures, None draws the default color of the side type
+    pub light: Option<usize>, // index into Map::lights, None takes the light of the region the side is seen from
 }
 
 impl Side {
     pub fn new(point1: Point, point2: Point, side_type: ShapeType, height: f64) -> Self {
-        return Side {
-            point1: point1,
-            point2: point2,
-            side_type: side_type,
+        Side {
+            point1,
+            point2,
+            side_type,
             angle_in_world: ((point1.x - point2.x) / (point1.y - point2.y)).atan(),
-            height: height,
-        };
+            height,
+            length: (point2 - point1).length(),
+            texture: None,
+            light: None,
+        }
     }
 }
 
@@ -60,6 +95,11 @@ impl Side {
 pub struct Shape {
     pub sides: Vec<Side>,
     pub shape_type: ShapeType,
+    // cap on top of a block, seen from above; walls and sectors ignore these
+    pub top_color: Option<u32>, // None draws the default color of blocks
+    pub top_texture: Option<usize>, // index into Map::textures, takes precedence over top_color
+    pub mover: Option<Mover>,   // makes a block a door or lift
+    pub light: Option<usize>,   // index into Map::lights, for the top; the sides have their own
 }
 
 impl Shape {
@@ -73,71 +113,247 @@ impl Shape {
         for i in 0..points.len() {
             point1 = point2;
             point2 = *points.get(i)?;
-            sides.push(Side::new(point1, point2, shape_type, height)); // TODO make height passable to method
+            sides.push(Side::new(point1, point2, shape_type, height));
         }
         Some(Shape {
-            sides: sides,
-            shape_type: shape_type,
+            sides,
+            shape_type,
+            top_color: None,
+            top_texture: None,
+            mover: None,
+            light: None,
         })
     }
+
+    pub fn contains(&self, point: Point) -> bool {
+        polygon_contains(&self.sides, point)
+    }
+}
+
+// even-odd rule: a horizontal ray from the point crosses the outline an odd number of times iff the point is inside
+pub fn polygon_contains(sides: &[Side], point: Point) -> bool {
+    let mut inside = false;
+    for side in sides {
+        let (p1, p2) = (side.point1, side.point2);
+        if (p1.y > point.y) != (p2.y > point.y) {
+            let crossing_x = p1.x + (point.y - p1.y) / (p2.y - p1.y) * (p2.x - p1.x);
+            if crossing_x > point.x {
+                inside = !inside;
+            }
+        }
+    }
+    inside
+}
+
+// floor and ceiling of a region of the map
+#[derive(Clone, Copy, PartialEq)]
+pub struct Surfaces {
+    pub floor_height: f64,
+    pub ceiling_height: f64,
+    pub floor_color: u32,
+    pub ceiling_color: Option<u32>, // None leaves the background visible (open sky)
+    pub light: usize, // index into Map::lights; also lights the sides, sprites and enemies inside the region
+}
+
+pub struct Sector {
+    pub outline: Shape,
+    pub surfaces: Surfaces,
+}
+
+// everything about a map that is not geometry; every field can be left out of the map file
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MapMetadata {
+    pub name: String,
+    pub author: String,
+    pub level_height: f64, // height of the border and of every wall that does not set its own
+    pub wall_default_color: u32,
+    pub block_default_color: u32,
+    pub background_color: u32,
+    pub distance_darkness_coefficient: f64,
+    pub player_start: Point,
+    pub player_start_angle: f64, // in degrees, 0 looks along +x
+    pub player_radius: f64,      // how close the player can get to any side
+    pub texture_size: f64, // world units covered by one repetition of a texture, both along and up a side
+    pub player_eye_height: f64, // camera height above the floor the player starts on
+    pub player_health: f64,
+    // surfaces everywhere outside of sectors; the ceiling is at level_height
+    pub floor_height: f64,
+    pub floor_color: u32,
+    pub ceiling_color: u32,
+    pub sky: bool, // no ceiling, the background color shows instead
+    pub light: Light,
+}
+
+impl Default for MapMetadata {
+    fn default() -> Self {
+        Self {
+            name: String::from("unnamed"),
+            author: String::from("unknown"),
+            level_height: 25.0,
+            wall_default_color: 0x00ff00,
+            block_default_color: 0x0000ff,
+            background_color: 0x222222,
+            distance_darkness_coefficient: 0.025,
+            player_start: Point { x: 0.0, y: 0.0 },
+            player_start_angle: -90.0,
+            player_radius: 5.0,
+            texture_size: 25.0,
+            player_eye_height: 10.0,
+            player_health: 100.0,
+            floor_height: 0.0,
+            floor_color: 0x505050,
+            ceiling_color: 0x303040,
+            sky: false,
+            light: Light::default(),
+        }
+    }
 }
 
-// TODO master shape and side list
+// flat image that always faces the camera (decorations, items, ...)
+#[derive(Clone)]
+pub struct Sprite {
+    pub position: Point,
+    pub width: f64,
+    pub height: f64,
+    pub elevation: f64, // how far above the floor below it the bottom of the image is
+    pub texture: usize, // index into Map::textures
+}
 
 pub struct Map {
-    pub id: usize,
+    pub metadata: MapMetadata,
     pub border: Shape, // mainly for topdown renderer (maybe change to rectangle?)
     pub walls: Vec<Shape>,
     pub blocks: Vec<Shape>,
-    //pub points_in_border: Vec<Point>,
+    pub sectors: Vec<Sector>,
+    pub sprites: Vec<Sprite>,
+    pub enemies: Vec<Enemy>, // as placed in the map file; Game works on its own copies
+    pub exit: Option<Shape>, // walking into it completes the level
+    pub textures: Vec<Texture>,
+    pub lights: Vec<Light>,  // the first one is the light of the metadata
+    pub side_grid: SideGrid, // index over the sides of walls, blocks and sectors for ray casting and collision
+}
+
+// everything a map has besides its metadata and border; what a map doesn't have can be left at the default
+#[derive(Default)]
+pub struct MapContents {
+    pub walls: Vec<Shape>,
+    pub blocks: Vec<Shape>,
+    pub sectors: Vec<Sector>,
+    pub sprites: Vec<Sprite>,
+    pub enemies: Vec<Enemy>,
+    pub exit: Option<Shape>,
+    pub textures: Vec<Texture>,
+    pub lights: Vec<Light>, // the lights of sides, blocks and sectors; they count from 1 in Map::lights
 }
 
 impl Map {
-    pub fn new() -> Option<Self> {
-        Some(Self {
-            id: 0,
-            border: Shape::from_points(
-                vec![
-                    Point { x: 200.0, y: 100.0 },
-                    Point { x: 250.0, y: 200.0 },
-                    Point { x: 350.0, y: 200.0 },
-                    Point { x: 275.0, y: 250.0 },
-                    Point { x: 300.0, y: 350.0 },
-                    Point { x: 200.0, y: 300.0 },
-                    Point { x: 100.0, y: 350.0 },
-                    Point { x: 125.0, y: 250.0 },
-                    Point { x: 50.0, y: 200.0 },
-                    Point { x: 150.0, y: 200.0 },
-                ],
-                ShapeType::Wall,
-                LEVEL_HEIGHT,
-            )?,
-            walls: vec![Shape::from_points(
-                vec![
-                    Point { x: 200.0, y: 100.0 },
-                    Point { x: 250.0, y: 200.0 },
-                    Point { x: 350.0, y: 200.0 },
-                    Point { x: 275.0, y: 250.0 },
-                    Point { x: 300.0, y: 350.0 },
-                    Point { x: 200.0, y: 300.0 },
-                    Point { x: 100.0, y: 350.0 },
-                    Point { x: 125.0, y: 250.0 },
-                    Point { x: 50.0, y: 200.0 },
-                    Point { x: 150.0, y: 200.0 },
-                ],
-                ShapeType::Wall,
-                LEVEL_HEIGHT,
-            )?],
-            blocks: vec![Shape::from_points(
-                vec![
-                    Point { x: 200.0, y: 200.0 },
-                    Point { x: 175.0, y: 200.0 },
-                    Point { x: 175.0, y: 175.0 },
-                ],
-                ShapeType::Block,
-                25.0,
-            )?],
-            //points_in_border: Vec::new(),
-        })
+    // only the side grid is built here; the light of the metadata goes in front of the other lights
+    pub fn new(metadata: MapMetadata, border: Shape, contents: MapContents) -> Self {
+        let MapContents {
+            walls,
+            blocks,
+            sectors,
+            sprites,
+            enemies,
+            exit,
+            textures,
+            lights,
+        } = contents;
+        let side_grid = SideGrid::build(MapShapes {
+            walls: &walls,
+            blocks: &blocks,
+            sectors: &sectors,
+        });
+        let lights = std::iter::once(metadata.light).chain(lights).collect();
+        let mut map = Self {
+            metadata,
+            border,
+            walls,
+            blocks,
+            sectors,
+            sprites,
+            enemies,
+            exit,
+            textures,
+            lights,
+            side_grid,
+        };
+        // enemies stand on top of whatever they are placed on, however high
+        for i in 0..map.enemies.len() {
+            map.enemies[i].feet_height =
+                map.floor_height_at(map.enemies[i].position, f64::INFINITY);
+        }
+        map
+    }
+
+    pub fn is_in_exit(&self, point: Point) -> bool {
+        self.exit
+            .as_ref()
+            .is_some_and(|exit| polygon_contains(&exit.sides, point))
+    }
+
+    // level of one of the lights at time seconds into the game
+    pub fn light_level(&self, light: usize, time: f64) -> f64 {
+        self.lights[light].level_at(time)
+    }
+
+    pub fn shapes(&self) -> MapShapes<'_> {
+        MapShapes {
+            walls: &self.walls,
+            blocks: &self.blocks,
+            sectors: &self.sectors,
+        }
+    }
+
+    // reads a map from a RON file (see assets/maps for examples)
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, MapError> {
+        let map_file = MapFile::read(path.as_ref())?;
+        map_file.into_map(path.as_ref())
+    }
+
+    pub fn default_surfaces(&self) -> Surfaces {
+        Surfaces {
+            floor_height: self.metadata.floor_height,
+            ceiling_height: self.metadata.level_height,
+            floor_color: self.metadata.floor_color,
+            ceiling_color: (!self.metadata.sky).then_some(self.metadata.ceiling_color),
+            light: 0,
+        }
+    }
+
+    // animates doors and lifts; a closing door that the player or an enemy is in the way of opens again.
+    // actors are the center and radius of everyone who can be in the way
+    pub fn update_movers(&mut self, delta_time: f64, actors: &[(Point, f64)]) {
+        for block in &mut self.blocks {
+            let Shape { sides, mover, .. } = block;
+            if let Some(mover) = mover {
+                let blocked = mover.state == MoverState::Closing
+                    && actors
+                        .iter()
+                        .any(|&(center, radius)| circle_overlaps_shape(sides, center, radius));
+                mover.update(delta_time, blocked);
+                mover.apply(sides);
+            }
+        }
+    }
+
+    // height of whatever the feet rest on at the point: the floor of the sector, or the highest block top
+    // that is not above max_height (blocks reaching higher are walls the point is stuck in, not floors)
+    pub fn floor_height_at(&self, point: Point, max_height: f64) -> f64 {
+        self.blocks
+            .iter()
+            .filter(|block| block.sides[0].height <= max_height && block.contains(point))
+            .map(|block| block.sides[0].height)
+            .fold(self.surfaces_at(point).floor_height, f64::max)
+    }
+
+    // where sectors overlap the one defined last in the map file wins
+    pub fn surfaces_at(&self, point: Point) -> Surfaces {
+        self.sectors
+            .iter()
+            .rev()
+            .find(|sector| sector.outline.contains(point))
+            .map_or_else(|| self.default_surfaces(), |sector| sector.surfaces)
     }
 }
@@ -0,0 +1,777 @@
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use ron::extensions::Extensions;
+use serde::{Deserialize, Serialize};
+
+use super::enemy::{Enemy, EnemyState};
+use super::light::Light;
+use super::map::{
+    Map, MapContents, MapMetadata, Point, Sector, Shape, ShapeType, Sprite, Surfaces,
+};
+use super::mover::{Mover, MoverKind};
+use crate::render::texture::{Texture, TextureError};
+
+const TEXTURE_DIRECTORY: &str = "../textures"; // relative to the directory of the map file
+
+// on-disk layout of a map; kept separate from Map so the runtime representation (sides, angles, ...) can change freely
+#[derive(Serialize, Deserialize)]
+pub struct MapFile {
+    pub metadata: MapMetadata,
+    pub border: Vec<Point>,
+    #[serde(default)]
+    pub walls: Vec<ShapeFile>,
+    #[serde(default)]
+    pub blocks: Vec<ShapeFile>,
+    #[serde(default)]
+    pub sectors: Vec<SectorFile>,
+    #[serde(default)]
+    pub sprites: Vec<SpriteFile>,
+    #[serde(default)]
+    pub enemies: Vec<EnemyFile>,
+    #[serde(default)]
+    pub exit: Option<Vec<Point>>, // area that completes the level
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ShapeFile {
+    pub points: Vec<Point>,
+    #[serde(default)]
+    pub height: Option<f64>, // falls back to the level height from the metadata
+    #[serde(default)]
+    pub texture: Option<String>, // file name in assets/textures, used for every side of the shape
+    #[serde(default)]
+    pub side_textures: Vec<Option<String>>, // per-side overrides; entry i is the side ending at points[i]
+    #[serde(default)]
+    pub top_color: Option<u32>, // only blocks have a visible top
+    #[serde(default)]
+    pub top_texture: Option<String>,
+    #[serde(default)]
+    pub mover: Option<MoverFile>, // only blocks can move
+    #[serde(default)]
+    pub light: Option<Light>, // None takes the light of the region the sides are seen from
+    #[serde(default)]
+    pub side_lights: Vec<Option<Light>>, // per-side overrides, in the same order as side_textures
+}
+
+// the shape written in the map is the closed position, opening moves it towards open_height and open_offset
+#[derive(Serialize, Deserialize)]
+pub struct MoverFile {
+    pub kind: MoverKind,
+    #[serde(default)]
+    pub open_height: f64, // sinks into the floor by default
+    #[serde(default)]
+    pub open_offset: Option<Point>,
+    #[serde(default = "default_mover_speed")]
+    pub speed: f64, // world units per second
+    #[serde(default = "default_mover_wait")]
+    pub wait: f64, // seconds
+}
+
+fn default_mover_speed() -> f64 {
+    20.0
+}
+
+fn default_mover_wait() -> f64 {
+    3.0
+}
+
+// every surface left out falls back to the map-wide value from the metadata
+#[derive(Serialize, Deserialize)]
+pub struct SectorFile {
+    pub points: Vec<Point>,
+    #[serde(default)]
+    pub floor_height: Option<f64>,
+    #[serde(default)]
+    pub ceiling_height: Option<f64>,
+    #[serde(default)]
+    pub floor_color: Option<u32>,
+    #[serde(default)]
+    pub ceiling_color: Option<u32>,
+    #[serde(default)]
+    pub sky: Option<bool>,
+    #[serde(default)]
+    pub light: Option<Light>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SpriteFile {
+    pub position: Point,
+    pub width: f64,
+    pub height: f64,
+    #[serde(default)]
+    pub elevation: f64,
+    pub texture: String, // file name in assets/textures; magenta pixels are transparent
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct EnemyFile {
+    pub position: Point,
+    pub texture: String, // file name in assets/textures; magenta pixels are transparent
+    #[serde(default)]
+    pub dead_texture: Option<String>, // left lying around after death; without one the enemy disappears
+    #[serde(default = "default_enemy_health")]
+    pub health: f64,
+    #[serde(default = "default_enemy_speed")]
+    pub speed: f64, // world units per second
+    #[serde(default = "default_enemy_damage")]
+    pub damage: f64, // per hit, the player starts with player_health
+    #[serde(default = "default_enemy_width")]
+    pub width: f64, // also sets how wide the enemy is for collision
+    #[serde(default = "default_enemy_height")]
+    pub height: f64,
+}
+
+fn default_enemy_health() -> f64 {
+    50.0
+}
+
+fn default_enemy_speed() -> f64 {
+    20.0
+}
+
+fn default_enemy_damage() -> f64 {
+    10.0
+}
+
+fn default_enemy_width() -> f64 {
+    8.0
+}
+
+fn default_enemy_height() -> f64 {
+    12.0
+}
+
+#[derive(Debug)]
+pub enum MapError {
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    Parse {
+        path: PathBuf,
+        source: ron::error::SpannedError,
+    },
+    Invalid {
+        path: PathBuf,
+        reason: String,
+    },
+    Texture(TextureError),
+}
+
+impl fmt::Display for MapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MapError::Io { path, source } => {
+                write!(f, "could not read map {}: {source}", path.display())
+            }
+            MapError::Parse { path, source } => {
+                write!(f, "could not parse map {}: {source}", path.display())
+            }
+            MapError::Invalid { path, reason } => {
+                write!(f, "invalid map {}: {reason}", path.display())
+            }
+            MapError::Texture(source) => write!(f, "{source}"),
+        }
+    }
+}
+
+impl std::error::Error for MapError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            MapError::Io { source, .. } => Some(source),
+            MapError::Parse { source, .. } => Some(source),
+            MapError::Invalid { .. } => None,
+            MapError::Texture(source) => Some(source),
+        }
+    }
+}
+
+impl MapFile {
+    pub fn read(path: &Path) -> Result<Self, MapError> {
+        let contents = fs::read_to_string(path).map_err(|source| MapError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        // implicit_some lets map files write `height: 30.0` instead of `height: Some(30.0)`
+        ron::Options::default()
+            .with_default_extension(Extensions::IMPLICIT_SOME)
+            .from_str(&contents)
+            .map_err(|source| MapError::Parse {
+                path: path.to_path_buf(),
+                source,
+            })
+    }
+
+    pub fn into_map(self, path: &Path) -> Result<Map, MapError> {
+        let invalid = |reason: String| MapError::Invalid {
+            path: path.to_path_buf(),
+            reason,
+        };
+
+        let metadata = self.metadata;
+        if !metadata.level_height.is_finite() || metadata.level_height <= 0.0 {
+            return Err(invalid(format!(
+                "level_height must be a positive number, got {}",
+                metadata.level_height
+            )));
+        }
+        // brightness divides by it, smaller values darken more slowly with distance
+        if !metadata.distance_darkness_coefficient.is_finite()
+            || metadata.distance_darkness_coefficient <= 0.0
+        {
+            return Err(invalid(format!(
+                "distance_darkness_coefficient must be a positive number, got {}",
+                metadata.distance_darkness_coefficient
+            )));
+        }
+        if !metadata.player_radius.is_finite() || metadata.player_radius < 0.0 {
+            return Err(invalid(format!(
+                "player_radius must not be negative, got {}",
+                metadata.player_radius
+            )));
+        }
+        if !metadata.texture_size.is_finite() || metadata.texture_size <= 0.0 {
+            return Err(invalid(format!(
+                "texture_size must be a positive number, got {}",
+                metadata.texture_size
+            )));
+        }
+
+        if !metadata.player_eye_height.is_finite() || metadata.player_eye_height < 0.0 {
+            return Err(invalid(format!(
+                "player_eye_height must not be negative, got {}",
+                metadata.player_eye_height
+            )));
+        }
+        if !metadata.player_health.is_finite() || metadata.player_health <= 0.0 {
+            return Err(invalid(format!(
+                "player_health must be a positive number, got {}",
+                metadata.player_health
+            )));
+        }
+        if !metadata.floor_height.is_finite() {
+            return Err(invalid(format!(
+                "floor_height must be a number, got {}",
+                metadata.floor_height
+            )));
+        }
+        let start = metadata.player_start;
+        if !start.x.is_finite() || !start.y.is_finite() {
+            return Err(invalid(format!(
+                "player_start must be a point, got ({}, {})",
+                start.x, start.y
+            )));
+        }
+        if !metadata.player_start_angle.is_finite() {
+            return Err(invalid(format!(
+                "player_start_angle must be a number, got {}",
+                metadata.player_start_angle
+            )));
+        }
+        check_light(&metadata.light).map_err(|reason| invalid(format!("light: {reason}")))?;
+        // Map::new puts the light of the metadata in front of these, at index 0
+        let mut lights = Vec::new();
+
+        let border = build_shape(self.border, ShapeType::Wall, metadata.level_height)
+            .map_err(|reason| invalid(format!("border: {reason}")))?;
+        // the topdown view scales the map by the size of the border
+        let (min_x, max_x, min_y, max_y) = border.sides.iter().fold(
+            (
+                f64::INFINITY,
+                f64::NEG_INFINITY,
+                f64::INFINITY,
+                f64::NEG_INFINITY,
+            ),
+            |(min_x, max_x, min_y, max_y), side| {
+                let p = side.point1;
+                (
+                    min_x.min(p.x),
+                    max_x.max(p.x),
+                    min_y.min(p.y),
+                    max_y.max(p.y),
+                )
+            },
+        );
+        let (width, height) = (max_x - min_x, max_y - min_y);
+        if !(width.is_finite() && width > 0.0 && height.is_finite() && height > 0.0) {
+            return Err(invalid(format!(
+                "border must have a positive width and height, got {width} by {height}"
+            )));
+        }
+        if !border.contains(start) {
+            return Err(invalid(format!(
+                "player_start ({}, {}) is outside of the border",
+                start.x, start.y
+            )));
+        }
+
+        let mut textures = TextureLibrary::new(path);
+
+        let mut walls = Vec::with_capacity(self.walls.len());
+        for (i, wall) in self.walls.into_iter().enumerate() {
+            walls.push(wall.into_shape(
+                ShapeType::Wall,
+                metadata.level_height,
+                &mut textures,
+                &mut lights,
+                |reason| invalid(format!("wall {i}: {reason}")),
+            )?);
+        }
+
+        let mut blocks = Vec::with_capacity(self.blocks.len());
+        for (i, block) in self.blocks.into_iter().enumerate() {
+            blocks.push(block.into_shape(
+                ShapeType::Block,
+                metadata.level_height,
+                &mut textures,
+                &mut lights,
+                |reason| invalid(format!("block {i}: {reason}")),
+            )?);
+        }
+
+        let mut default_surfaces = Surfaces {
+            floor_height: metadata.floor_height,
+            ceiling_height: metadata.level_height,
+            floor_color: metadata.floor_color,
+            ceiling_color: Some(metadata.ceiling_color),
+            light: 0,
+        };
+        let mut sectors = Vec::with_capacity(self.sectors.len());
+        for (i, sector) in self.sectors.into_iter().enumerate() {
+            sectors.push(
+                sector
+                    .into_sector(&default_surfaces, metadata.sky, &mut lights)
+                    .map_err(|reason| invalid(format!("sector {i}: {reason}")))?,
+            );
+        }
+        if metadata.sky {
+            default_surfaces.ceiling_color = None;
+        }
+        check_surfaces(&default_surfaces).map_err(invalid)?;
+
+        let mut sprites = Vec::with_capacity(self.sprites.len());
+        for (i, sprite) in self.sprites.into_iter().enumerate() {
+            sprites.push(sprite.into_sprite(&mut textures, |reason| {
+                invalid(format!("sprite {i}: {reason}"))
+            })?);
+        }
+
+        let mut enemies = Vec::with_capacity(self.enemies.len());
+        for (i, enemy) in self.enemies.into_iter().enumerate() {
+            enemies.push(enemy.into_enemy(&mut textures, |reason| {
+                invalid(format!("enemy {i}: {reason}"))
+            })?);
+        }
+
+        let exit = self
+            .exit
+            .map(|points| build_shape(points, ShapeType::Sector, metadata.level_height))
+            .transpose()
+            .map_err(|reason| invalid(format!("exit: {reason}")))?;
+
+        Ok(Map::new(
+            metadata,
+            border,
+            MapContents {
+                walls,
+                blocks,
+                sectors,
+                sprites,
+                enemies,
+                exit,
+                textures: textures.textures,
+                lights,
+            },
+        ))
+    }
+}
+
+impl ShapeFile {
+    fn into_shape(
+        self,
+        shape_type: ShapeType,
+        default_height: f64,
+        textures: &mut TextureLibrary,
+        lights: &mut Vec<Light>,
+        invalid: impl Fn(String) -> MapError,
+    ) -> Result<Shape, MapError> {
+        for (name, count) in [
+            ("side_textures", self.side_textures.len()),
+            ("side_lights", self.side_lights.len()),
+        ] {
+            if count > self.points.len() {
+                return Err(invalid(format!(
+                    "has {count} {name} but only {} sides",
+                    self.points.len()
+                )));
+            }
+        }
+        let height = self.height.unwrap_or(default_height);
+        let mut shape = build_shape(self.points, shape_type, height).map_err(&invalid)?;
+
+        let shape_texture = match &self.texture {
+            Some(name) => Some(textures.index_of(name)?),
+            None => None,
+        };
+        let mut add_light = |light: Light| {
+            check_light(&light).map_err(|reason| invalid(format!("light: {reason}")))?;
+            lights.push(light);
+            Ok(lights.len()) // index into Map::lights, behind the light of the metadata
+        };
+        let shape_light = self.light.map(&mut add_light).transpose()?;
+        // from_points starts with the closing side (last point -> first point), which ends at points[0]
+        for (i, side) in shape.sides.iter_mut().enumerate() {
+            side.texture = match self.side_textures.get(i) {
+                Some(Some(name)) => Some(textures.index_of(name)?),
+                _ => shape_texture,
+            };
+            side.light = match self.side_lights.get(i) {
+                Some(Some(light)) => Some(add_light(*light)?),
+                _ => shape_light,
+            };
+        }
+        if let Some(mover) = self.mover {
+            if shape_type != ShapeType::Block {
+                return Err(invalid("only blocks can be doors or lifts".to_string()));
+            }
+            shape.mover = Some(mover.into_mover(&shape).map_err(&invalid)?);
+        }
+        shape.light = shape_light;
+        shape.top_color = self.top_color;
+        shape.top_texture = match &self.top_texture {
+            Some(name) => Some(textures.index_of(name)?),
+            None => None,
+        };
+        Ok(shape)
+    }
+}
+
+impl EnemyFile {
+    fn into_enemy(
+        self,
+        textures: &mut TextureLibrary,
+        invalid: impl Fn(String) -> MapError,
+    ) -> Result<Enemy, MapError> {
+        if !self.position.x.is_finite() || !self.position.y.is_finite() {
+            return Err(invalid("position must be a number".to_string()));
+        }
+        for (name, value) in [
+            ("health", self.health),
+            ("width", self.width),
+            ("height", self.height),
+        ] {
+            if !value.is_finite() || value <= 0.0 {
+                return Err(invalid(format!(
+                    "{name} must be a positive number, got {value}"
+                )));
+            }
+        }
+        for (name, value) in [("speed", self.speed), ("damage", self.damage)] {
+            if !value.is_finite() || value < 0.0 {
+                return Err(invalid(format!("{name} must not be negative, got {value}")));
+            }
+        }
+        let dead_texture = match &self.dead_texture {
+            Some(name) => Some(textures.index_of(name)?),
+            None => None,
+        };
+        Ok(Enemy {
+            position: self.position,
+            health: self.health,
+            state: EnemyState::Idle,
+            speed: self.speed,
+            damage: self.damage,
+            radius: self.width / 2.0,
+            width: self.width,
+            height: self.height,
+            texture: textures.index_of(&self.texture)?,
+            dead_texture,
+            attack_cooldown: 0.0,
+            feet_height: 0.0, // Map::new puts it onto the floor below
+        })
+    }
+}
+
+impl MoverFile {
+    fn into_mover(self, shape: &Shape) -> Result<Mover, String> {
+        let open_offset = self.open_offset.unwrap_or(Point { x: 0.0, y: 0.0 });
+        if !self.open_height.is_finite() || self.open_height < 0.0 {
+            return Err(format!(
+                "mover open_height must not be negative, got {}",
+                self.open_height
+            ));
+        }
+        if !open_offset.x.is_finite() || !open_offset.y.is_finite() {
+            return Err("mover open_offset must be a number".to_string());
+        }
+        if !self.speed.is_finite() || self.speed <= 0.0 {
+            return Err(format!(
+                "mover speed must be a positive number, got {}",
+                self.speed
+            ));
+        }
+        if !self.wait.is_finite() || self.wait < 0.0 {
+            return Err(format!(
+                "mover wait must not be negative, got {}",
+                self.wait
+            ));
+        }
+        Ok(Mover::new(
+            self.kind,
+            shape,
+            self.open_height,
+            open_offset,
+            self.speed,
+            self.wait,
+        ))
+    }
+}
+
+impl SectorFile {
+    fn into_sector(
+        self,
+        defaults: &Surfaces,
+        default_sky: bool,
+        lights: &mut Vec<Light>,
+    ) -> Result<Sector, String> {
+        let light = match self.light {
+            Some(light) => {
+                check_light(&light).map_err(|reason| format!("light: {reason}"))?;
+                lights.push(light);
+                lights.len() // index into Map::lights, behind the light of the metadata
+            }
+            None => defaults.light,
+        };
+        let outline = build_shape(self.points, ShapeType::Sector, defaults.ceiling_height)?;
+        let surfaces = Surfaces {
+            floor_height: self.floor_height.unwrap_or(defaults.floor_height),
+            ceiling_height: self.ceiling_height.unwrap_or(defaults.ceiling_height),
+            floor_color: self.floor_color.unwrap_or(defaults.floor_color),
+            ceiling_color: if self.sky.unwrap_or(default_sky) {
+                None
+            } else {
+                Some(
+                    self.ceiling_color
+                        .or(defaults.ceiling_color)
+                        .unwrap_or_default(),
+                )
+            },
+            light,
+        };
+        check_surfaces(&surfaces)?;
+        Ok(Sector { outline, surfaces })
+    }
+}
+
+impl SpriteFile {
+    fn into_sprite(
+        self,
+        textures: &mut TextureLibrary,
+        invalid: impl Fn(String) -> MapError,
+    ) -> Result<Sprite, MapError> {
+        if !self.position.x.is_finite() || !self.position.y.is_finite() {
+            return Err(invalid("position must be a number".to_string()));
+        }
+        if !self.width.is_finite()
+            || self.width <= 0.0
+            || !self.height.is_finite()
+            || self.height <= 0.0
+        {
+            return Err(invalid(format!(
+                "width and height must be positive numbers, got {} and {}",
+                self.width, self.height
+            )));
+        }
+        if !self.elevation.is_finite() {
+            return Err(invalid(format!(
+                "elevation must be a number, got {}",
+                self.elevation
+            )));
+        }
+        Ok(Sprite {
+            position: self.position,
+            width: self.width,
+            height: self.height,
+            elevation: self.elevation,
+            texture: textures.index_of(&self.texture)?,
+        })
+    }
+}
+
+fn check_surfaces(surfaces: &Surfaces) -> Result<(), String> {
+    if !surfaces.floor_height.is_finite() || !surfaces.ceiling_height.is_finite() {
+        return Err("floor and ceiling height must be numbers".to_string());
+    }
+    if surfaces.ceiling_color.is_some() && surfaces.ceiling_height <= surfaces.floor_height {
+        return Err(format!(
+            "ceiling ({}) must be above the floor ({})",
+            surfaces.ceiling_height, surfaces.floor_height
+        ));
+    }
+    Ok(())
+}
+
+fn check_light(light: &Light) -> Result<(), String> {
+    for (name, value) in [("level", light.level), ("low_level", light.low_level)] {
+        if !(0.0..=1.0).contains(&value) {
+            return Err(format!("{name} must be between 0 and 1, got {value}"));
+        }
+    }
+    if !light.period.is_finite() || light.period <= 0.0 {
+        return Err(format!(
+            "period must be a positive number, got {}",
+            light.period
+        ));
+    }
+    if !light.phase.is_finite() {
+        return Err(format!("phase must be a number, got {}", light.phase));
+    }
+    Ok(())
+}
+
+// loads every texture file only once, no matter how many sides use it
+struct TextureLibrary {
+    directory: PathBuf,
+    names: Vec<String>,
+    textures: Vec<Texture>,
+}
+
+impl TextureLibrary {
+    fn new(map_path: &Path) -> Self {
+        let map_directory = map_path.parent().unwrap_or(Path::new("."));
+        Self {
+            directory: map_directory.join(TEXTURE_DIRECTORY),
+            names: Vec::new(),
+            textures: Vec::new(),
+        }
+    }
+
+    fn index_of(&mut self, name: &str) -> Result<usize, MapError> {
+        if let Some(index) = self.names.iter().position(|n| n == name) {
+            return Ok(index);
+        }
+        let texture = Texture::load(self.directory.join(name)).map_err(MapError::Texture)?;
+        self.names.push(name.to_string());
+        self.textures.push(texture);
+        Ok(self.textures.len() - 1)
+    }
+}
+
+fn build_shape(points: Vec<Point>, shape_type: ShapeType, height: f64) -> Result<Shape, String> {
+    if points.len() < 3 {
+        return Err(format!("needs at least 3 points, got {}", points.len()));
+    }
+    if !height.is_finite() || height <= 0.0 {
+        return Err(format!("height must be a positive number, got {height}"));
+    }
+    Shape::from_points(points, shape_type, height)
+        .ok_or_else(|| "could not build shape".to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn map_file(metadata: MapMetadata) -> MapFile {
+        MapFile {
+            metadata,
+            // around the default player_start at the origin
+            border: vec![
+                Point { x: -50.0, y: -50.0 },
+                Point { x: 50.0, y: -50.0 },
+                Point { x: 50.0, y: 50.0 },
+            ],
+            walls: Vec::new(),
+            blocks: Vec::new(),
+            sectors: Vec::new(),
+            sprites: Vec::new(),
+            enemies: Vec::new(),
+            exit: None,
+        }
+    }
+
+    #[test]
+    fn distance_darkness_coefficient_must_be_positive() {
+        let path = Path::new("test.map");
+        assert!(map_file(MapMetadata::default()).into_map(path).is_ok());
+        for coefficient in [0.0, -0.1, f64::NAN] {
+            let metadata = MapMetadata {
+                distance_darkness_coefficient: coefficient,
+                ..MapMetadata::default()
+            };
+            assert!(
+                matches!(
+                    map_file(metadata).into_map(path),
+                    Err(MapError::Invalid { .. })
+                ),
+                "{coefficient}"
+            );
+        }
+    }
+
+    fn is_invalid(map_file: MapFile) -> bool {
+        matches!(
+            map_file.into_map(Path::new("test.map")),
+            Err(MapError::Invalid { .. })
+        )
+    }
+
+    #[test]
+    fn player_start_must_be_inside_the_border() {
+        for start in [
+            Point { x: 60.0, y: 0.0 },
+            Point { x: 0.0, y: 40.0 },
+            Point {
+                x: f64::NAN,
+                y: 0.0,
+            },
+            Point {
+                x: 0.0,
+                y: f64::INFINITY,
+            },
+        ] {
+            let metadata = MapMetadata {
+                player_start: start,
+                ..MapMetadata::default()
+            };
+            assert!(is_invalid(map_file(metadata)), "{start:?}");
+        }
+        let metadata = MapMetadata {
+            player_start_angle: f64::NAN,
+            ..MapMetadata::default()
+        };
+        assert!(is_invalid(map_file(metadata)));
+    }
+
+    #[test]
+    fn border_must_have_width_and_height() {
+        for points in [
+            [(-50.0, 0.0), (0.0, 0.0), (50.0, 0.0)],
+            [(0.0, -50.0), (0.0, 0.0), (0.0, 50.0)],
+            [(0.0, 0.0), (0.0, 0.0), (0.0, 0.0)],
+        ] {
+            let mut map_file = map_file(MapMetadata::default());
+            map_file.border = points.iter().map(|&(x, y)| Point { x, y }).collect();
+            match map_file.into_map(Path::new("test.map")) {
+                Err(MapError::Invalid { reason, .. }) => {
+                    assert!(reason.starts_with("border must have"), "{reason}")
+                }
+                _ => panic!("{points:?} was accepted"),
+            }
+        }
+    }
+
+    #[test]
+    fn bundled_maps_load() {
+        for name in ["default.map", "emptyexample.map"] {
+            let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("assets/maps")
+                .join(name);
+            if let Err(error) = Map::load(&path) {
+                panic!("{error}");
+            }
+        }
+    }
+}
@@ -1,6 +1,17 @@
+mod collision;
+pub mod enemy;
 pub mod gamestate;
+pub mod hitscan;
+pub mod input;
+pub mod light;
 pub mod map;
+mod map_loader;
+pub mod mover;
 pub mod player;
+pub mod random;
+pub mod side_grid;
+#[cfg(test)]
+pub mod test_maps;
+pub mod weapon;
 
 pub use gamestate::Game;
-pub use map::Map;
@@ -0,0 +1,263 @@
+use serde::{Deserialize, Serialize};
+
+use super::map::{Point, Shape, Side};
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum MoverKind {
+    Door, // goes back up when it would close on the player
+    Lift, // carries the player standing on it
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum MoverState {
+    Closed,
+    Opening,
+    Open { time_left: f64 },
+    Closing,
+}
+
+// animates the height and position of a block between its closed shape (as written in the map) and its open one
+#[derive(Clone)]
+pub struct Mover {
+    pub kind: MoverKind,
+    pub state: MoverState,
+    pub progress: f64, // 0 is closed, 1 is open
+    pub closed_height: f64,
+    pub open_height: f64,
+    pub open_offset: Point, // how far the block slides when it opens
+    pub travel_time: f64,   // seconds from closed to open
+    pub wait: f64,          // seconds it stays open before closing again
+    closed_sides: Vec<(Point, Point)>,
+}
+
+impl Mover {
+    pub fn new(
+        kind: MoverKind,
+        shape: &Shape,
+        open_height: f64,
+        open_offset: Point,
+        speed: f64,
+        wait: f64,
+    ) -> Self {
+        let closed_height = shape.sides[0].height;
+        // speed is in world units per second for whichever of height and position has farther to go
+        let distance = (open_height - closed_height)
+            .abs()
+            .max(open_offset.length());
+        Mover {
+            kind,
+            state: MoverState::Closed,
+            progress: 0.0,
+            closed_height,
+            open_height,
+            open_offset,
+            travel_time: distance / speed,
+            wait,
+            closed_sides: shape
+                .sides
+                .iter()
+                .map(|side| (side.point1, side.point2))
+                .collect(),
+        }
+    }
+
+    // the use key opens a closed mover and sends a closing one back up
+    pub fn activate(&mut self) {
+        match self.state {
+            MoverState::Closed | MoverState::Closing => self.state = MoverState::Opening,
+            MoverState::Open { .. } => {
+                self.state = MoverState::Open {
+                    time_left: self.wait,
+                }
+            }
+            MoverState::Opening => {}
+        }
+    }
+
+    // blocked is true while the player is in the way of the block
+    pub fn update(&mut self, delta_time: f64, blocked: bool) {
+        let step = if self.travel_time > 0.0 {
+            delta_time / self.travel_time
+        } else {
+            1.0
+        };
+        self.state = match self.state {
+            MoverState::Closed => MoverState::Closed,
+            MoverState::Opening => {
+                self.progress = (self.progress + step).min(1.0);
+                if self.progress >= 1.0 {
+                    MoverState::Open {
+                        time_left: self.wait,
+                    }
+                } else {
+                    MoverState::Opening
+                }
+            }
+            MoverState::Open { time_left } if time_left > delta_time => MoverState::Open {
+                time_left: time_left - delta_time,
+            },
+            MoverState::Open { .. } => MoverState::Closing,
+            MoverState::Closing if blocked && self.kind == MoverKind::Door => MoverState::Opening,
+            MoverState::Closing => {
+                self.progress = (self.progress - step).max(0.0);
+                if self.progress <= 0.0 {
+                    MoverState::Closed
+                } else {
+                    MoverState::Closing
+                }
+            }
+        };
+    }
+
+    // moves the sides of the block to where the current progress puts them
+    pub fn apply(&self, sides: &mut [Side]) {
+        let height = self.closed_height + (self.open_height - self.closed_height) * self.progress;
+        let offset = self.open_offset * self.progress;
+        for (side, &(point1, point2)) in sides.iter_mut().zip(&self.closed_sides) {
+            side.point1 = point1 + offset;
+            side.point2 = point2 + offset;
+            side.height = height;
+        }
+    }
+
+    pub fn is_moving(&self) -> bool {
+        matches!(self.state, MoverState::Opening | MoverState::Closing)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::game::map::{MapContents, MapMetadata, ShapeType};
+    use crate::game::test_maps;
+
+    const CLOSED_HEIGHT: f64 = 20.0;
+    const OPEN_HEIGHT: f64 = 2.0;
+    const SPEED: f64 = 18.0; // one second from closed to open
+    const WAIT: f64 = 2.0;
+
+    fn block() -> Shape {
+        test_maps::rectangle(
+            Point { x: 0.0, y: 0.0 },
+            Point { x: 10.0, y: 10.0 },
+            ShapeType::Block,
+            CLOSED_HEIGHT,
+        )
+    }
+
+    fn mover(kind: MoverKind, open_offset: Point) -> Mover {
+        Mover::new(kind, &block(), OPEN_HEIGHT, open_offset, SPEED, WAIT)
+    }
+
+    fn closing_door() -> Mover {
+        let mut door = mover(MoverKind::Door, Point { x: 0.0, y: 0.0 });
+        door.activate();
+        door.update(1.0, false);
+        door.update(WAIT, false);
+        assert_eq!(door.state, MoverState::Closing);
+        door
+    }
+
+    #[test]
+    fn opens_waits_and_closes() {
+        let mut door = mover(MoverKind::Door, Point { x: 0.0, y: 0.0 });
+        assert_eq!(door.travel_time, 1.0);
+        door.update(0.5, false);
+        assert_eq!(door.state, MoverState::Closed);
+
+        door.activate();
+        door.update(0.5, false);
+        assert_eq!(door.state, MoverState::Opening);
+        assert_eq!(door.progress, 0.5);
+        door.update(0.5, false);
+        assert_eq!(door.state, MoverState::Open { time_left: WAIT });
+
+        door.update(0.5, false);
+        assert_eq!(door.state, MoverState::Open { time_left: 1.5 });
+        door.update(1.5, false);
+        assert_eq!(door.state, MoverState::Closing);
+        assert_eq!(door.progress, 1.0);
+
+        door.update(0.5, false);
+        assert_eq!(door.state, MoverState::Closing);
+        assert_eq!(door.progress, 0.5);
+        door.update(0.5, false);
+        assert_eq!(door.state, MoverState::Closed);
+        assert_eq!(door.progress, 0.0);
+    }
+
+    #[test]
+    fn blocked_door_opens_again_but_lift_does_not() {
+        let mut door = closing_door();
+        door.update(0.25, false);
+        door.update(0.25, true);
+        assert_eq!(door.state, MoverState::Opening);
+        assert_eq!(door.progress, 0.75);
+
+        let mut lift = closing_door();
+        lift.kind = MoverKind::Lift;
+        lift.update(0.25, true);
+        assert_eq!(lift.state, MoverState::Closing);
+        assert_eq!(lift.progress, 0.75);
+    }
+
+    #[test]
+    fn activating_keeps_an_open_mover_open() {
+        let mut door = mover(MoverKind::Door, Point { x: 0.0, y: 0.0 });
+        door.activate();
+        door.update(1.0, false);
+        door.update(1.5, false);
+        door.activate();
+        assert_eq!(door.state, MoverState::Open { time_left: WAIT });
+
+        let mut door = closing_door();
+        door.update(0.5, false);
+        door.activate();
+        assert_eq!(door.state, MoverState::Opening);
+    }
+
+    #[test]
+    fn apply_moves_the_sides() {
+        let mut shape = block();
+        let mut door = mover(MoverKind::Door, Point { x: 4.0, y: 0.0 });
+        door.progress = 0.5;
+        door.apply(&mut shape.sides);
+        for (side, original) in shape.sides.iter().zip(&block().sides) {
+            assert_eq!(side.height, (CLOSED_HEIGHT + OPEN_HEIGHT) / 2.0);
+            assert_eq!(side.point1, original.point1 + Point { x: 2.0, y: 0.0 });
+            assert_eq!(side.point2, original.point2 + Point { x: 2.0, y: 0.0 });
+        }
+
+        door.progress = 0.0;
+        door.apply(&mut shape.sides);
+        for (side, original) in shape.sides.iter().zip(&block().sides) {
+            assert_eq!(side.height, CLOSED_HEIGHT);
+            assert_eq!(side.point1, original.point1);
+        }
+    }
+
+    #[test]
+    fn map_reopens_a_door_closing_on_anyone() {
+        let mut door = block();
+        door.mover = Some(closing_door());
+        let contents = MapContents {
+            blocks: vec![door],
+            ..MapContents::default()
+        };
+        let mut map = test_maps::room(100.0, MapMetadata::default(), contents);
+        // the player is away, but someone else stands under the door
+        let inside = Point { x: 5.0, y: 5.0 };
+        let outside = Point { x: 50.0, y: 50.0 };
+        map.update_movers(0.5, &[(outside, 1.0), (inside, 1.0)]);
+        let door = &map.blocks[0];
+        assert_eq!(door.mover.as_ref().unwrap().state, MoverState::Opening);
+        assert_eq!(door.sides[0].height, OPEN_HEIGHT);
+
+        map.update_movers(1.0, &[(outside, 1.0)]);
+        map.update_movers(WAIT, &[(outside, 1.0)]);
+        map.update_movers(0.5, &[(outside, 1.0)]);
+        let door = &map.blocks[0];
+        assert_eq!(door.mover.as_ref().unwrap().state, MoverState::Closing);
+        assert_eq!(door.sides[0].height, (CLOSED_HEIGHT + OPEN_HEIGHT) / 2.0);
+    }
+}
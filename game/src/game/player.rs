@@ -1,84 +1,191 @@
-use super::map::Map;
-use crate::{SCREEN_HEIGHT, SCREEN_WIDTH, game::map::LEVEL_HEIGHT};
-use minifb::{Key, MouseMode, Window};
+use super::collision::move_with_collision;
+use super::input::{Action, InputState};
+use super::map::{Map, Point};
+use super::weapon::Weapon;
 use std::f64::consts::PI;
 
-const ROTATIONSPEED: f64 = 2.0;
-const MOVESPEED: f64 = 0.5;
-const FLYUPANDDOWNSPEED: f64 = 0.5;
+// speeds are per second; update() scales them by the frame time
+pub(crate) const ROTATIONSPEED: f64 = 2.0;
+pub(crate) const MOVESPEED: f64 = 30.0;
+const FLYUPANDDOWNSPEED: f64 = 30.0;
+const KEY_TURN_SPEED: f64 = 6.0; // radians per second when turning with Q and E
+const KEY_PITCH_SPEED: f64 = 1.5; // radians per second when looking up and down with the arrow keys
+pub const MOUSE_SENSITIVITY: f64 = 0.003; // radians per pixel the mouse moves, unless the settings say otherwise
+pub const MAX_PITCH: f64 = 0.8; // radians up or down; y-shearing gets too distorted beyond that
+const GRAVITY: f64 = 200.0; // world units per second squared
+const JUMP_SPEED: f64 = 60.0; // upwards velocity right after jumping, reaches 9 units high
+pub(crate) const STEP_HEIGHT: f64 = 4.0; // highest ledge the player (and enemies) walk onto without jumping
+const HEAD_ROOM: f64 = 1.0; // how close the eye gets to the ceiling
 
 #[derive(Clone, Copy)]
 pub struct Player {
     pub position_x: f64,
     pub position_y: f64,
     pub view_height: f64,
+    pub eye_height: f64, // view_height above the feet
+    pub vertical_velocity: f64,
+    pub on_ground: bool,
+    pub noclip: bool, // flies freely with Jump and FlyDown and passes through everything
+    pub health: f64,
+    pub weapons: [Weapon; 2], // selected with the number keys
+    pub current_weapon: usize,
     pub velocity_x: f64,
     pub velocity_y: f64,
     pub view_angle: f64,
-    pub last_mouse_x: f32,
+    pub pitch: f64,     // radians, positive looks up
+    pub invert_y: bool, // moving the mouse up looks down
+    pub mouse_sensitivity: f64,
+    pub radius: f64,
 }
 
 impl Player {
-    pub fn new() -> Self {
-        let pa: f64 = -PI / 2.0;
+    pub fn new(map: &Map) -> Self {
+        let pa: f64 = map.metadata.player_start_angle.to_radians();
         Self {
-            position_x: 187.5,
-            position_y: 225.0,
-            view_height: 0.0,
+            position_x: map.metadata.player_start.x,
+            position_y: map.metadata.player_start.y,
+            // the start may be on top of a block, however high
+            view_height: map.floor_height_at(map.metadata.player_start, f64::INFINITY)
+                + map.metadata.player_eye_height,
+            eye_height: map.metadata.player_eye_height,
+            vertical_velocity: 0.0,
+            on_ground: true,
+            noclip: false,
+            health: map.metadata.player_health,
+            weapons: [Weapon::pistol(), Weapon::shotgun()],
+            current_weapon: 0,
             velocity_x: pa.cos() * ROTATIONSPEED,
             velocity_y: pa.sin() * ROTATIONSPEED,
             view_angle: pa,
-            last_mouse_x: SCREEN_WIDTH as f32 / 2.0,
+            pitch: 0.0,
+            invert_y: false,
+            mouse_sensitivity: MOUSE_SENSITIVITY,
+            radius: map.metadata.player_radius,
         }
     }
 
-    pub fn update(&mut self, window: &Window, map: &Map) {
-        if let Some((mx, _my)) = window.get_mouse_pos(MouseMode::Pass) {
+    pub fn weapon(&self) -> &Weapon {
+        &self.weapons[self.current_weapon]
+    }
+
+    pub fn weapon_mut(&mut self) -> &mut Weapon {
+        &mut self.weapons[self.current_weapon]
+    }
+
+    // delta_time is the time since the last update in seconds
+    pub fn update(&mut self, input: &InputState, map: &Map, delta_time: f64) {
+        let (dx, dy) = input.mouse_delta;
+        if dx != 0.0 || dy != 0.0 {
             self.check_angle();
-            let dx = mx - self.last_mouse_x; // mouse delta
-            self.view_angle += dx as f64 * 0.003; // sensitivity
+            self.view_angle += dx * self.mouse_sensitivity;
 
-            self.last_mouse_x = mx; // store for next frame
+            // screen y grows downwards, so moving the mouse up looks up
+            let pitch_direction = if self.invert_y { 1.0 } else { -1.0 };
+            self.look_up(dy * self.mouse_sensitivity * pitch_direction);
             self.update_dir();
         }
-        if window.is_key_down(Key::Q) {
+        if input.is_down(Action::LookUp) {
+            self.look_up(KEY_PITCH_SPEED * delta_time);
+        }
+        if input.is_down(Action::LookDown) {
+            self.look_up(-KEY_PITCH_SPEED * delta_time);
+        }
+        if input.is_down(Action::TurnLeft) {
             self.check_angle();
-            self.view_angle -= 0.1;
+            self.view_angle -= KEY_TURN_SPEED * delta_time;
             self.update_dir();
         }
 
-        if window.is_key_down(Key::E) {
+        if input.is_down(Action::TurnRight) {
             self.check_angle();
-            self.view_angle += 0.1;
+            self.view_angle += KEY_TURN_SPEED * delta_time;
             self.update_dir();
         }
 
-        if window.is_key_down(Key::W) {
-            self.position_x += self.velocity_x * MOVESPEED;
-            self.position_y += self.velocity_y * MOVESPEED;
+        // sum up the movement first so collision can slide along sides as a whole
+        let mut movement = Point { x: 0.0, y: 0.0 };
+        if input.is_down(Action::MoveForward) {
+            movement.x += self.velocity_x * MOVESPEED;
+            movement.y += self.velocity_y * MOVESPEED;
+        }
+
+        if input.is_down(Action::StrafeLeft) {
+            movement.x += self.velocity_y * MOVESPEED;
+            movement.y -= self.velocity_x * MOVESPEED;
+        }
+        if input.is_down(Action::StrafeRight) {
+            movement.x -= self.velocity_y * MOVESPEED;
+            movement.y += self.velocity_x * MOVESPEED;
+        }
+
+        if input.is_down(Action::MoveBackward) {
+            movement.x -= self.velocity_x * MOVESPEED;
+            movement.y -= self.velocity_y * MOVESPEED;
         }
 
-        if window.is_key_down(Key::A) {
-            self.position_x += self.velocity_y * MOVESPEED;
-            self.position_y -= self.velocity_x * MOVESPEED;
+        if self.noclip {
+            self.position_x += movement.x * delta_time;
+            self.position_y += movement.y * delta_time;
+            if input.is_down(Action::Jump) {
+                self.view_height += FLYUPANDDOWNSPEED * delta_time;
+            }
+            if input.is_down(Action::FlyDown) {
+                self.view_height -= FLYUPANDDOWNSPEED * delta_time;
+            }
+            return;
         }
-        if window.is_key_down(Key::D) {
-            self.position_x -= self.velocity_y * MOVESPEED;
-            self.position_y += self.velocity_x * MOVESPEED;
+
+        let feet_height = self.view_height - self.eye_height;
+        if movement.x != 0.0 || movement.y != 0.0 {
+            let position = move_with_collision(
+                map,
+                Point {
+                    x: self.position_x,
+                    y: self.position_y,
+                },
+                movement * delta_time,
+                self.radius,
+                feet_height + STEP_HEIGHT,
+            );
+            self.position_x = position.x;
+            self.position_y = position.y;
         }
 
-        if window.is_key_down(Key::S) {
-            self.position_x -= self.velocity_x * MOVESPEED;
-            self.position_y -= self.velocity_y * MOVESPEED;
+        if self.on_ground && input.is_down(Action::Jump) {
+            self.vertical_velocity = JUMP_SPEED;
         }
+        self.fall(map, feet_height, delta_time);
+    }
+
+    // applies gravity and lands on whatever is below; ledges up to STEP_HEIGHT above the feet are stepped onto
+    fn fall(&mut self, map: &Map, feet_height: f64, delta_time: f64) {
+        let position = Point {
+            x: self.position_x,
+            y: self.position_y,
+        };
+        let ground = map.floor_height_at(position, feet_height + STEP_HEIGHT);
 
-        if window.is_key_down(Key::Space) {
-            self.view_height += FLYUPANDDOWNSPEED;
+        self.vertical_velocity -= GRAVITY * delta_time;
+        let mut feet_height = feet_height + self.vertical_velocity * delta_time;
+        self.on_ground = feet_height <= ground;
+        if self.on_ground {
+            feet_height = ground;
+            self.vertical_velocity = 0.0;
         }
 
-        if window.is_key_down(Key::LeftShift) {
-            self.view_height -= FLYUPANDDOWNSPEED;
+        // bump the head instead of flying through the ceiling
+        let surfaces = map.surfaces_at(position);
+        if surfaces.ceiling_color.is_some()
+            && feet_height + self.eye_height > surfaces.ceiling_height - HEAD_ROOM
+        {
+            feet_height = (surfaces.ceiling_height - HEAD_ROOM - self.eye_height).max(ground);
+            self.vertical_velocity = self.vertical_velocity.min(0.0);
         }
+        self.view_height = feet_height + self.eye_height;
+    }
+
+    fn look_up(&mut self, angle: f64) {
+        self.pitch = (self.pitch + angle).clamp(-MAX_PITCH, MAX_PITCH);
     }
 
     fn check_angle(&mut self) {
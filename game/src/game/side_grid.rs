@@ -0,0 +1,514 @@
+use super::map::{Point, Sector, Shape, ShapeType, Side};
+use crate::render::raycast::{RayHit, intersect};
+
+const MIN_CELL_SIZE: f64 = 1.0;
+const SIDES_PER_CELL: f64 = 2.0; // rough target for how many sides share a cell
+const CELL_PADDING: f64 = 1e-6; // sides ending right at a cell boundary are put into both cells to survive rounding
+const GRID_MARGIN: f64 = 1.0; // keeps sides on the outline of the map away from the edge of the grid, where rays stop
+
+// identifies a side of the map without copying it; shape_index points into Map::walls, Map::blocks or Map::sectors
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct SideRef {
+    pub shape_type: ShapeType,
+    pub shape_index: usize,
+    pub side_index: usize,
+}
+
+// uniform grid over the map; every cell lists the sides whose bounding box touches it,
+// so a ray only has to test the sides in the cells it passes through.
+// sides of doors and lifts are listed in every cell they can move through, so the grid never has to be rebuilt
+pub struct SideGrid {
+    origin: Point, // corner with the smallest x and y
+    cell_size: f64,
+    columns: usize,
+    rows: usize,
+    cells: Vec<Vec<SideRef>>,
+}
+
+// borrowed view of the shapes the grid indexes, so rays can be cast while the map is being built
+#[derive(Clone, Copy)]
+pub struct MapShapes<'a> {
+    pub walls: &'a [Shape],
+    pub blocks: &'a [Shape],
+    pub sectors: &'a [Sector],
+}
+
+impl<'a> MapShapes<'a> {
+    pub fn side(&self, side_ref: SideRef) -> &'a Side {
+        let shape = match side_ref.shape_type {
+            ShapeType::Wall => &self.walls[side_ref.shape_index],
+            ShapeType::Block => &self.blocks[side_ref.shape_index],
+            ShapeType::Sector => &self.sectors[side_ref.shape_index].outline,
+        };
+        &shape.sides[side_ref.side_index]
+    }
+
+    // bounding box of the side, including everywhere a door or lift can slide it to
+    fn swept_bounds(&self, side_ref: SideRef, side: &Side) -> (Point, Point) {
+        let offset = match side_ref.shape_type {
+            ShapeType::Block => self.blocks[side_ref.shape_index]
+                .mover
+                .as_ref()
+                .map_or(Point { x: 0.0, y: 0.0 }, |mover| mover.open_offset),
+            ShapeType::Wall | ShapeType::Sector => Point { x: 0.0, y: 0.0 },
+        };
+        let points = [
+            side.point1,
+            side.point2,
+            side.point1 + offset,
+            side.point2 + offset,
+        ];
+        let min = Point {
+            x: points.iter().map(|p| p.x).fold(f64::INFINITY, f64::min),
+            y: points.iter().map(|p| p.y).fold(f64::INFINITY, f64::min),
+        };
+        let max = Point {
+            x: points.iter().map(|p| p.x).fold(f64::NEG_INFINITY, f64::max),
+            y: points.iter().map(|p| p.y).fold(f64::NEG_INFINITY, f64::max),
+        };
+        (min, max)
+    }
+
+    fn all_sides(&self) -> impl Iterator<Item = (SideRef, &'a Side)> {
+        let refs = |shape_type: ShapeType, shapes: Vec<&'a Shape>| {
+            shapes
+                .into_iter()
+                .enumerate()
+                .flat_map(move |(shape_index, shape)| {
+                    shape
+                        .sides
+                        .iter()
+                        .enumerate()
+                        .map(move |(side_index, side)| {
+                            let side_ref = SideRef {
+                                shape_type,
+                                shape_index,
+                                side_index,
+                            };
+                            (side_ref, side)
+                        })
+                })
+        };
+        refs(ShapeType::Wall, self.walls.iter().collect())
+            .chain(refs(ShapeType::Block, self.blocks.iter().collect()))
+            .chain(refs(
+                ShapeType::Sector,
+                self.sectors.iter().map(|s| &s.outline).collect(),
+            ))
+    }
+}
+
+impl SideGrid {
+    pub fn build(shapes: MapShapes) -> Self {
+        let mut min = Point {
+            x: f64::INFINITY,
+            y: f64::INFINITY,
+        };
+        let mut max = Point {
+            x: f64::NEG_INFINITY,
+            y: f64::NEG_INFINITY,
+        };
+        let mut side_count = 0;
+        for (side_ref, side) in shapes.all_sides() {
+            let (side_min, side_max) = shapes.swept_bounds(side_ref, side);
+            min.x = min.x.min(side_min.x);
+            min.y = min.y.min(side_min.y);
+            max.x = max.x.max(side_max.x);
+            max.y = max.y.max(side_max.y);
+            side_count += 1;
+        }
+        if side_count == 0 {
+            return SideGrid {
+                origin: Point { x: 0.0, y: 0.0 },
+                cell_size: MIN_CELL_SIZE,
+                columns: 0,
+                rows: 0,
+                cells: Vec::new(),
+            };
+        }
+
+        min.x -= GRID_MARGIN;
+        min.y -= GRID_MARGIN;
+        max.x += GRID_MARGIN;
+        max.y += GRID_MARGIN;
+
+        // aim for a few sides per cell on average
+        let width = (max.x - min.x).max(MIN_CELL_SIZE);
+        let height = (max.y - min.y).max(MIN_CELL_SIZE);
+        let cell_size = (width * height * SIDES_PER_CELL / side_count as f64)
+            .sqrt()
+            .max(MIN_CELL_SIZE);
+        let columns = (width / cell_size).floor() as usize + 1;
+        let rows = (height / cell_size).floor() as usize + 1;
+
+        let mut grid = SideGrid {
+            origin: min,
+            cell_size,
+            columns,
+            rows,
+            cells: vec![Vec::new(); columns * rows],
+        };
+        for (side_ref, side) in shapes.all_sides() {
+            let (side_min, side_max) = shapes.swept_bounds(side_ref, side);
+            let padding = Point {
+                x: CELL_PADDING,
+                y: CELL_PADDING,
+            };
+            let (first_column, first_row) = grid.cell_of(side_min - padding);
+            let (last_column, last_row) = grid.cell_of(side_max + padding);
+            for row in first_row..=last_row {
+                for column in first_column..=last_column {
+                    grid.cells[row * columns + column].push(side_ref);
+                }
+            }
+        }
+        grid
+    }
+
+    // cell containing the point, clamped to the grid
+    fn cell_of(&self, point: Point) -> (usize, usize) {
+        let column = ((point.x - self.origin.x) / self.cell_size).floor();
+        let row = ((point.y - self.origin.y) / self.cell_size).floor();
+        (
+            (column.max(0.0) as usize).min(self.columns - 1),
+            (row.max(0.0) as usize).min(self.rows - 1),
+        )
+    }
+
+    // every side whose cells overlap the box, each listed once
+    pub fn sides_in_box(&self, min: Point, max: Point) -> Vec<SideRef> {
+        if self.cells.is_empty() {
+            return Vec::new();
+        }
+        let (first_column, first_row) = self.cell_of(min);
+        let (last_column, last_row) = self.cell_of(max);
+        let mut sides = Vec::new();
+        for row in first_row..=last_row {
+            for column in first_column..=last_column {
+                sides.extend_from_slice(&self.cells[row * self.columns + column]);
+            }
+        }
+        sides.sort_unstable();
+        sides.dedup();
+        sides
+    }
+
+    // walks the cells along the ray (Amanatides & Woo) and hands every hit to visit, nearest first;
+    // visit returns false to stop the walk, e.g. once a wall is hit
+    pub fn cast_ray<'a>(
+        &self,
+        shapes: MapShapes<'a>,
+        origin: Point,
+        angle: f64,
+        max_distance: f64,
+        mut visit: impl FnMut(SideRef, RayHit<'a>) -> bool,
+    ) {
+        if self.cells.is_empty() {
+            return;
+        }
+        let direction = Point {
+            x: angle.cos(),
+            y: angle.sin(),
+        };
+
+        // find where the ray enters the grid, it might start outside of it
+        let grid_max = Point {
+            x: self.origin.x + self.columns as f64 * self.cell_size,
+            y: self.origin.y + self.rows as f64 * self.cell_size,
+        };
+        let Some((mut t, t_leave)) = slab_range(origin, direction, self.origin, grid_max) else {
+            return;
+        };
+        let t_end = t_leave.min(max_distance);
+        if t > t_end {
+            return;
+        }
+
+        let entry = origin + direction * t;
+        let (mut column, mut row) = self.cell_of(entry);
+
+        let step_column: isize = if direction.x >= 0.0 { 1 } else { -1 };
+        let step_row: isize = if direction.y >= 0.0 { 1 } else { -1 };
+        // distance along the ray to cross one cell horizontally / vertically
+        let t_delta_x = (self.cell_size / direction.x).abs();
+        let t_delta_y = (self.cell_size / direction.y).abs();
+        // distance along the ray to the next vertical / horizontal cell boundary
+        let next_boundary_x = self.origin.x
+            + (column as f64 + if step_column > 0 { 1.0 } else { 0.0 }) * self.cell_size;
+        let next_boundary_y =
+            self.origin.y + (row as f64 + if step_row > 0 { 1.0 } else { 0.0 }) * self.cell_size;
+        let mut t_max_x = if direction.x != 0.0 {
+            (next_boundary_x - origin.x) / direction.x
+        } else {
+            f64::INFINITY
+        };
+        let mut t_max_y = if direction.y != 0.0 {
+            (next_boundary_y - origin.y) / direction.y
+        } else {
+            f64::INFINITY
+        };
+
+        let mut hits: Vec<(SideRef, RayHit<'a>)> = Vec::new();
+        loop {
+            let t_cell_exit = t_max_x.min(t_max_y).min(t_end);
+
+            // only keep hits inside this cell; sides spanning several cells are found again in the next ones
+            hits.clear();
+            for &side_ref in &self.cells[row * self.columns + column] {
+                if let Some(hit) = intersect(origin, angle, shapes.side(side_ref))
+                    && hit.distance >= t
+                    && (hit.distance < t_cell_exit
+                        || (t_cell_exit == t_end && hit.distance <= t_end))
+                {
+                    hits.push((side_ref, hit));
+                }
+            }
+            hits.sort_by(|a, b| a.1.distance.total_cmp(&b.1.distance));
+            for (side_ref, hit) in hits.drain(..) {
+                if !visit(side_ref, hit) {
+                    return;
+                }
+            }
+
+            if t_cell_exit >= t_end {
+                return;
+            }
+            t = t_cell_exit;
+            if t_max_x < t_max_y {
+                let next = column as isize + step_column;
+                if next < 0 || next >= self.columns as isize {
+                    return;
+                }
+                column = next as usize;
+                t_max_x += t_delta_x;
+            } else {
+                let next = row as isize + step_row;
+                if next < 0 || next >= self.rows as isize {
+                    return;
+                }
+                row = next as usize;
+                t_max_y += t_delta_y;
+            }
+        }
+    }
+}
+
+// range of distances along the ray that lie inside the box, None if the ray misses it
+fn slab_range(origin: Point, direction: Point, min: Point, max: Point) -> Option<(f64, f64)> {
+    let mut t_min: f64 = 0.0;
+    let mut t_max = f64::INFINITY;
+    for (o, d, lo, hi) in [
+        (origin.x, direction.x, min.x, max.x),
+        (origin.y, direction.y, min.y, max.y),
+    ] {
+        if d == 0.0 {
+            if o < lo || o > hi {
+                return None;
+            }
+        } else {
+            let (t1, t2) = ((lo - o) / d, (hi - o) / d);
+            t_min = t_min.max(t1.min(t2));
+            t_max = t_max.min(t1.max(t2));
+        }
+    }
+    (t_min <= t_max).then_some((t_min, t_max))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::game::test_maps;
+    use std::f64::consts::PI;
+
+    fn rectangle(min: Point, max: Point, shape_type: ShapeType) -> Shape {
+        test_maps::rectangle(min, max, shape_type, 10.0)
+    }
+
+    // an outer wall, a long wall across many cells and a few blocks
+    fn test_shapes() -> (Vec<Shape>, Vec<Shape>) {
+        let walls = vec![
+            rectangle(
+                Point { x: 0.0, y: 0.0 },
+                Point { x: 100.0, y: 100.0 },
+                ShapeType::Wall,
+            ),
+            Shape::from_points(
+                vec![Point { x: 10.0, y: 70.0 }, Point { x: 90.0, y: 75.0 }],
+                ShapeType::Wall,
+                10.0,
+            )
+            .unwrap(),
+        ];
+        let blocks = vec![
+            rectangle(
+                Point { x: 20.0, y: 20.0 },
+                Point { x: 30.0, y: 30.0 },
+                ShapeType::Block,
+            ),
+            rectangle(
+                Point { x: 60.0, y: 20.0 },
+                Point { x: 65.0, y: 50.0 },
+                ShapeType::Block,
+            ),
+            rectangle(
+                Point { x: 40.0, y: 40.0 },
+                Point { x: 50.0, y: 45.0 },
+                ShapeType::Block,
+            ),
+        ];
+        (walls, blocks)
+    }
+
+    fn grid_hits(
+        grid: &SideGrid,
+        shapes: MapShapes,
+        origin: Point,
+        angle: f64,
+    ) -> Vec<(SideRef, f64)> {
+        let mut hits = Vec::new();
+        grid.cast_ray(shapes, origin, angle, f64::INFINITY, |side_ref, hit| {
+            hits.push((side_ref, hit.distance));
+            true
+        });
+        hits
+    }
+
+    // every side tested, nearest first
+    fn brute_force_hits(shapes: MapShapes, origin: Point, angle: f64) -> Vec<(SideRef, f64)> {
+        let mut hits: Vec<(SideRef, f64)> = shapes
+            .all_sides()
+            .filter_map(|(side_ref, side)| {
+                intersect(origin, angle, side).map(|hit| (side_ref, hit.distance))
+            })
+            .collect();
+        hits.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)));
+        hits
+    }
+
+    // the grid hands the hits out nearest first, but hits at the same distance may come in any order
+    fn assert_same_hits(grid: &SideGrid, shapes: MapShapes, origin: Point, angle: f64) {
+        let mut hits = grid_hits(grid, shapes, origin, angle);
+        assert!(
+            hits.windows(2).all(|pair| pair[0].1 <= pair[1].1),
+            "hits out of order from {origin:?} at {angle}: {hits:?}"
+        );
+        hits.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)));
+        assert_eq!(
+            hits,
+            brute_force_hits(shapes, origin, angle),
+            "from {origin:?} at {angle}"
+        );
+    }
+
+    #[test]
+    fn cast_ray_matches_brute_force() {
+        let (walls, blocks) = test_shapes();
+        let shapes = MapShapes {
+            walls: &walls,
+            blocks: &blocks,
+            sectors: &[],
+        };
+        let grid = SideGrid::build(shapes);
+        for origin in [
+            Point { x: 5.0, y: 5.0 },
+            Point { x: 50.0, y: 50.0 },
+            Point { x: 35.5, y: 62.25 },
+            Point { x: 95.0, y: 90.0 },
+        ] {
+            for i in 0..360 {
+                assert_same_hits(&grid, shapes, origin, (i as f64 + 0.5).to_radians());
+            }
+        }
+    }
+
+    #[test]
+    fn cast_ray_on_cell_boundaries() {
+        let (walls, blocks) = test_shapes();
+        let shapes = MapShapes {
+            walls: &walls,
+            blocks: &blocks,
+            sectors: &[],
+        };
+        let grid = SideGrid::build(shapes);
+        // rays along the grid lines and through the corners of cells, starting on them
+        let mut corners = 0;
+        for i in 1..grid.columns.min(grid.rows) {
+            let on_boundary = grid.origin.x + i as f64 * grid.cell_size;
+            let corner = Point {
+                x: on_boundary,
+                y: grid.origin.y + i as f64 * grid.cell_size,
+            };
+            if !(1.0..99.0).contains(&corner.x) || !(1.0..99.0).contains(&corner.y) {
+                continue;
+            }
+            for angle in [0.0, PI / 4.0, PI / 2.0, PI, 1.25 * PI, 1.5 * PI] {
+                assert_same_hits(&grid, shapes, corner, angle);
+            }
+            corners += 1;
+        }
+        assert!(corners > 1, "the test map is too coarse for the grid");
+    }
+
+    #[test]
+    fn cast_ray_from_outside_the_grid() {
+        let (walls, blocks) = test_shapes();
+        let shapes = MapShapes {
+            walls: &walls,
+            blocks: &blocks,
+            sectors: &[],
+        };
+        let grid = SideGrid::build(shapes);
+        for (origin, angle) in [
+            (Point { x: -50.0, y: 25.0 }, 0.0),
+            (Point { x: 50.0, y: -30.0 }, PI / 2.0),
+            (Point { x: 150.0, y: 150.0 }, 1.2 * PI),
+            (Point { x: -20.0, y: -10.0 }, 0.3),
+        ] {
+            assert_same_hits(&grid, shapes, origin, angle);
+            assert!(!grid_hits(&grid, shapes, origin, angle).is_empty());
+        }
+        // pointing away from the map
+        assert!(grid_hits(&grid, shapes, Point { x: -50.0, y: 25.0 }, PI).is_empty());
+    }
+
+    #[test]
+    fn sides_across_several_cells_are_listed_once() {
+        let (walls, blocks) = test_shapes();
+        let shapes = MapShapes {
+            walls: &walls,
+            blocks: &blocks,
+            sectors: &[],
+        };
+        let grid = SideGrid::build(shapes);
+        let long_wall = SideRef {
+            shape_type: ShapeType::Wall,
+            shape_index: 1,
+            side_index: 0,
+        };
+        assert!(
+            grid.cells
+                .iter()
+                .filter(|cell| cell.contains(&long_wall))
+                .count()
+                > 1
+        );
+
+        let sides = grid.sides_in_box(Point { x: 0.0, y: 0.0 }, Point { x: 100.0, y: 100.0 });
+        assert_eq!(
+            sides,
+            shapes
+                .all_sides()
+                .map(|(side_ref, _)| side_ref)
+                .collect::<Vec<_>>()
+        );
+
+        // the ray runs along the long wall's cells and meets it only once
+        let hits = grid_hits(&grid, shapes, Point { x: 50.0, y: 50.0 }, PI / 2.0);
+        assert_eq!(
+            hits.iter()
+                .filter(|(side_ref, _)| *side_ref == long_wall)
+                .count(),
+            1
+        );
+    }
+}
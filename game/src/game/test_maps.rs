@@ -0,0 +1,43 @@
+// small maps built in code for the tests
+use super::enemy::{Enemy, EnemyState};
+use super::map::{Map, MapContents, MapMetadata, Point, Shape, ShapeType};
+
+pub fn rectangle(min: Point, max: Point, shape_type: ShapeType, height: f64) -> Shape {
+    let points = vec![
+        min,
+        Point { x: max.x, y: min.y },
+        max,
+        Point { x: min.x, y: max.y },
+    ];
+    Shape::from_points(points, shape_type, height).expect("a rectangle has points")
+}
+
+// square room from (0, 0) to (size, size); its border is also the first wall, the walls of contents come after it
+pub fn room(size: f64, metadata: MapMetadata, mut contents: MapContents) -> Map {
+    let border = rectangle(
+        Point { x: 0.0, y: 0.0 },
+        Point { x: size, y: size },
+        ShapeType::Wall,
+        metadata.level_height,
+    );
+    contents.walls.insert(0, border.clone());
+    Map::new(metadata, border, contents)
+}
+
+// 10 wide and 20 high, walks 20 units per second and hits for 10
+pub fn enemy(position: Point) -> Enemy {
+    Enemy {
+        position,
+        health: 10.0,
+        state: EnemyState::Idle,
+        speed: 20.0,
+        damage: 10.0,
+        radius: 5.0,
+        width: 10.0,
+        height: 20.0,
+        texture: 0,
+        dead_texture: None,
+        attack_cooldown: 0.0,
+        feet_height: 0.0,
+    }
+}
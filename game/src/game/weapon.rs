@@ -0,0 +1,53 @@
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Weapon {
+    pub name: &'static str,
+    pub damage: f64,        // per pellet that hits
+    pub pellets: u32,       // rays cast per shot
+    pub fire_interval: f64, // seconds between two shots
+    pub spread: f64,        // radians each pellet can stray from the view direction, to either side
+    pub range: f64,
+    pub ammo: u32,
+    pub cooldown: f64, // seconds until the weapon can fire again
+}
+
+impl Weapon {
+    pub fn pistol() -> Self {
+        Weapon {
+            name: "pistol",
+            damage: 15.0,
+            pellets: 1,
+            fire_interval: 0.4,
+            spread: 0.01,
+            range: 1_000.0,
+            ammo: 50,
+            cooldown: 0.0,
+        }
+    }
+
+    pub fn shotgun() -> Self {
+        Weapon {
+            name: "shotgun",
+            damage: 8.0,
+            pellets: 7,
+            fire_interval: 1.0,
+            spread: 0.08,
+            range: 400.0,
+            ammo: 12,
+            cooldown: 0.0,
+        }
+    }
+
+    pub fn update(&mut self, delta_time: f64) {
+        self.cooldown = (self.cooldown - delta_time).max(0.0);
+    }
+
+    // uses up a round if the weapon is ready; false means nothing is fired
+    pub fn try_fire(&mut self) -> bool {
+        if self.cooldown > 0.0 || self.ammo == 0 {
+            return false;
+        }
+        self.ammo -= 1;
+        self.cooldown = self.fire_interval;
+        true
+    }
+}
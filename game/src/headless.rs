@@ -0,0 +1,221 @@
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use crate::game::Game;
+use crate::game::map::Point;
+use crate::game::player::MAX_PITCH;
+use crate::render::hud::draw_hud;
+use crate::render::image_output::save_image;
+use crate::render::{ViewMode, render_init};
+use crate::settings::{parse_resolution, validate_view};
+use crate::{HORIZONTAL_FOV, MAP_PATH, SCREEN_HEIGHT, SCREEN_WIDTH};
+
+const USAGE: &str = "usage: game screenshot [options]
+renders frames without opening a window and writes them as .ppm or .png
+
+options:
+  --map <path>          map to load (default: assets/maps/default.map)
+  --output <path>       image to write (default: screenshot.png); with several frames
+                        a frame number is added before the extension
+  --position <x>,<y>    camera position (default: player start of the map)
+  --angle <degrees>     camera angle (default: player start angle of the map)
+  --height <height>     camera height (default: eye height above the start floor)
+  --pitch <degrees>     look up (positive) or down (negative) (default: 0)
+  --frames <n>          number of frames to render (default: 1)
+  --turn <degrees>      how far the camera turns between frames (default: 0)
+  --time <seconds>      game time the light effects are shown at (default: 0)
+  --resolution <w>x<h>  size of the image (default: 800x450)
+  --fov <degrees>       horizontal field of view (default: 90)
+  --render-scale <s>    render the camera view this much smaller and scale it up (default: 1)
+  --topdown             render the top down view instead of the camera view
+  --minimap             draw the minimap over the camera view
+  --rays                draw the cast rays in the top down view
+  --hud                 draw the hud over the view (the fps show 0)
+  --debug               draw the debug overlay (F3 in the game)
+  --threads <n>         threads rendering the camera view (default: one per core)";
+
+struct ScreenshotOptions {
+    map: PathBuf,
+    output: PathBuf,
+    position: Option<Point>,
+    angle: Option<f64>,
+    height: Option<f64>,
+    pitch: f64,
+    frames: usize,
+    turn: f64,
+    time: f64,
+    resolution: (usize, usize),
+    fov: f64, // degrees
+    render_scale: f64,
+    topdown: bool,
+    minimap: bool,
+    rays: bool,
+    hud: bool,
+    debug: bool,
+    threads: Option<usize>,
+}
+
+// entry point for `game screenshot ...`; args are everything after "screenshot"
+pub fn run(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let options = match parse_args(args)? {
+        Some(options) => options,
+        None => {
+            println!("{USAGE}");
+            return Ok(());
+        }
+    };
+
+    let mut game = Game::new(&options.map)?;
+    if let Some(position) = options.position {
+        game.player.position_x = position.x;
+        game.player.position_y = position.y;
+    }
+    if let Some(angle) = options.angle {
+        game.player.view_angle = angle.to_radians();
+    }
+    if let Some(height) = options.height {
+        game.player.view_height = height;
+    }
+    game.player.pitch = options.pitch.to_radians().clamp(-MAX_PITCH, MAX_PITCH);
+    game.time = options.time;
+
+    let (width, height) = options.resolution;
+    let mut renderer_data = render_init(width, height, options.fov.to_radians());
+    if options.topdown {
+        renderer_data.view_mode = ViewMode::TopDown;
+    }
+    renderer_data.show_minimap = options.minimap;
+    renderer_data.show_rays = options.rays;
+    renderer_data.show_debug = options.debug;
+    renderer_data.render_scale = options.render_scale;
+    if let Some(threads) = options.threads {
+        renderer_data.threads = threads;
+    }
+
+    let mut buffer: Vec<u32> = vec![0; width * height];
+    for frame in 0..options.frames {
+        crate::render::draw(&mut buffer, &renderer_data, &game);
+        if options.hud {
+            draw_hud(&mut buffer, &renderer_data, &game, 0.0);
+        }
+
+        let path = if options.frames == 1 {
+            options.output.clone()
+        } else {
+            numbered_path(&options.output, frame)
+        };
+        save_image(&path, &buffer, width, height)
+            .map_err(|e| format!("failed to write {}: {e}", path.display()))?;
+        println!("wrote {}", path.display());
+
+        game.player.view_angle += options.turn.to_radians();
+    }
+    Ok(())
+}
+
+// None means help was requested
+fn parse_args(args: &[String]) -> Result<Option<ScreenshotOptions>, String> {
+    let mut options = ScreenshotOptions {
+        map: PathBuf::from(MAP_PATH),
+        output: PathBuf::from("screenshot.png"),
+        position: None,
+        angle: None,
+        height: None,
+        pitch: 0.0,
+        frames: 1,
+        turn: 0.0,
+        time: 0.0,
+        resolution: (SCREEN_WIDTH, SCREEN_HEIGHT),
+        fov: HORIZONTAL_FOV.to_degrees(),
+        render_scale: 1.0,
+        topdown: false,
+        minimap: false,
+        rays: false,
+        hud: false,
+        debug: false,
+        threads: None,
+    };
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("{arg} needs a value\n\n{USAGE}"))
+        };
+        match arg.as_str() {
+            "--help" | "-h" => return Ok(None),
+            "--map" => options.map = PathBuf::from(value()?),
+            "--output" => options.output = PathBuf::from(value()?),
+            "--position" => {
+                let position = value()?;
+                let (x, y) = position
+                    .split_once(',')
+                    .ok_or_else(|| format!("--position expects <x>,<y>, got {position:?}"))?;
+                options.position = Some(Point {
+                    x: parse_number("--position", x)?,
+                    y: parse_number("--position", y)?,
+                });
+            }
+            "--angle" => options.angle = Some(parse_number(arg, value()?)?),
+            "--height" => options.height = Some(parse_number(arg, value()?)?),
+            "--pitch" => options.pitch = parse_number(arg, value()?)?,
+            "--frames" => {
+                let frames = value()?;
+                options.frames = frames
+                    .parse()
+                    .ok()
+                    .filter(|&frames| frames > 0)
+                    .ok_or_else(|| format!("--frames expects a positive number, got {frames:?}"))?;
+            }
+            "--threads" => {
+                let threads = value()?;
+                options.threads = Some(
+                    threads
+                        .parse()
+                        .ok()
+                        .filter(|&threads| threads > 0)
+                        .ok_or_else(|| {
+                            format!("--threads expects a positive number, got {threads:?}")
+                        })?,
+                );
+            }
+            "--turn" => options.turn = parse_number(arg, value()?)?,
+            "--time" => options.time = parse_number(arg, value()?)?,
+            "--resolution" => {
+                let resolution = value()?;
+                options.resolution = parse_resolution(resolution).ok_or_else(|| {
+                    format!("--resolution expects <width>x<height>, got {resolution:?}")
+                })?;
+            }
+            "--fov" => options.fov = parse_number(arg, value()?)?,
+            "--render-scale" => options.render_scale = parse_number(arg, value()?)?,
+            "--topdown" => options.topdown = true,
+            "--minimap" => options.minimap = true,
+            "--rays" => options.rays = true,
+            "--hud" => options.hud = true,
+            "--debug" => options.debug = true,
+            _ => return Err(format!("unknown option {arg:?}\n\n{USAGE}")),
+        }
+    }
+    validate_view(options.resolution, options.fov, options.render_scale)?;
+    Ok(Some(options))
+}
+
+fn parse_number(option: &str, value: &str) -> Result<f64, String> {
+    value
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|number| number.is_finite())
+        .ok_or_else(|| format!("{option} expects a number, got {value:?}"))
+}
+
+// screenshot.png -> screenshot_0003.png
+fn numbered_path(path: &Path, frame: usize) -> PathBuf {
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("frame");
+    let name = match path.extension().and_then(|e| e.to_str()) {
+        Some(extension) => format!("{stem}_{frame:04}.{extension}"),
+        None => format!("{stem}_{frame:04}"),
+    };
+    path.with_file_name(name)
+}
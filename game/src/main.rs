@@ -1,78 +1,118 @@
 #![allow(dead_code)]
 
+mod app;
 mod game;
+mod headless;
 mod render;
+mod settings;
+mod window_input;
 
-use crate::render::{RendererData, render_init};
-use minifb::{Key, MouseMode, Window, WindowOptions};
+use crate::app::App;
+use crate::settings::Settings;
+use minifb::{Window, WindowOptions};
 use std::f64::consts::PI;
-use std::time::{Duration, Instant};
+use std::time::Instant;
 
+// size and fov of the screenshots and tests; the window takes them from the settings
 const SCREEN_WIDTH: usize = 800;
 const SCREEN_HEIGHT: usize = 450;
-const TARGET_FPS: usize = 60;
 const HORIZONTAL_FOV: f64 = PI / 2.0;
-const BACKGROUND_COLOR: u32 = 0x222222;
-const DISTANCE_DARKNESS_COEFFICIENT: f64 = 0.025;
-const WALL_DEFAULT_COLOR: u32 = 0x00ff00;
-const BLOCK_DEFAULT_COLOR: u32 = 0x0000ff;
+const MAP_PATH: &str = "assets/maps/default.map";
+const MAP_DIRECTORY: &str = "assets/maps"; // the menu offers every map in here
+const SETTINGS_PATH: &str = "settings.ron";
+const MAX_FRAME_TIME: f64 = 0.1; // longer frames (window dragged, debugger, ...) are simulated as this long
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
+    //`game screenshot ...` renders without a window
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("screenshot") {
+        if let Err(e) = headless::run(&args[1..]) {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    let mut settings = match Settings::load(SETTINGS_PATH) {
+        Ok(settings) => settings,
+        Err(e) => {
+            eprintln!("{e}");
+            return Err(Box::new(e));
+        }
+    };
+    if let Err(e) = settings.override_from_args(&args) {
+        eprintln!("{e}");
+        std::process::exit(1);
+    }
+
     //for fps count
     let mut last_time = Instant::now();
     let mut frame_count = 0;
-    let mut fps_value = 0.0;
+    //for frame time
+    let mut last_frame = Instant::now();
+    let mut last_title = String::new();
 
     //creates window Safely
-    let mut window = match Window::new(
-        "game",
-        SCREEN_WIDTH,
-        SCREEN_HEIGHT,
-        WindowOptions::default(),
-    ) {
+    let (width, height) = settings.resolution;
+    let options = WindowOptions {
+        resize: true,
+        ..WindowOptions::default()
+    };
+    let mut window = match Window::new("game", width, height, options) {
         Ok(w) => w,
         Err(e) => {
             eprint!("failed to create Window");
             return Err(Box::new(e));
         }
     };
-    window.set_cursor_visibility(false); // hide mouse 
 
-    //to reduce CPU load by decreasing refresh rate oder so lol
-    window.set_target_fps(TARGET_FPS);
-
-    let mut buffer: Vec<u32> = vec![0; SCREEN_WIDTH * SCREEN_HEIGHT];
-
-    let mut game = game::Game::new();
-
-    let renderer_data: RendererData = render_init(
-        SCREEN_WIDTH,
-        SCREEN_HEIGHT,
-        HORIZONTAL_FOV,
-        BACKGROUND_COLOR,
-        DISTANCE_DARKNESS_COEFFICIENT,
-        WALL_DEFAULT_COLOR,
-        BLOCK_DEFAULT_COLOR,
-    );
+    let mut app = match App::new(MAP_DIRECTORY, SETTINGS_PATH.into(), settings) {
+        Ok(app) => app,
+        Err(e) => {
+            eprintln!("failed to read the map directory {MAP_DIRECTORY}: {e}");
+            return Err(Box::new(e));
+        }
+    };
+    // also limits the frame rate to reduce CPU load
+    app.apply_settings(&mut window, true);
+    let mut fps_value = 0.0;
+    let mut buffer: Vec<u32> = Vec::new();
 
-    while window.is_open() && !window.is_key_down(Key::Escape) {
-        game.update(&window);
+    while window.is_open() {
+        let now = Instant::now();
+        let delta_time = now
+            .duration_since(last_frame)
+            .as_secs_f64()
+            .min(MAX_FRAME_TIME);
+        last_frame = now;
 
-        render::draw(&mut buffer, &renderer_data, &mut game);
+        if !app.update(&mut window, delta_time) {
+            break;
+        }
+        // the frame size changes with the window and the settings
+        let (width, height) = (
+            app.renderer_data.screen_width,
+            app.renderer_data.screen_height,
+        );
+        buffer.resize(width * height, 0);
+        app.draw(&mut buffer, fps_value);
 
         //fps calc
         frame_count += 1;
-        let elapsed = last_time.elapsed().as_secs_f32();
+        let elapsed = last_time.elapsed().as_secs_f64();
 
         if elapsed >= 1.0 {
-            fps_value = frame_count as f32 / elapsed;
+            fps_value = frame_count as f64 / elapsed;
             frame_count = 0;
             last_time = Instant::now();
-
-            window.set_title(&format!("My Window | FPS: {:.1}", fps_value));
+        }
+        let title = app.title();
+        if title != last_title {
+            window.set_title(&title);
+            last_title = title;
         }
         //show buffer safely
-        if let Err(e) = window.update_with_buffer(&buffer, SCREEN_WIDTH, SCREEN_HEIGHT) {
+        if let Err(e) = window.update_with_buffer(&buffer, width, height) {
             eprintln!("failed to update the window: {e}");
             return Err(Box::new(e));
         }
@@ -1,188 +1,617 @@
+#![allow(clippy::needless_borrow, clippy::unnecessary_cast)]
+
 use core::f64;
-use std::collections::BinaryHeap;
+use std::sync::Mutex;
+use std::thread;
 
 use crate::game::Game;
-use crate::game::map::{LEVEL_HEIGHT, Point, Shape, ShapeType, Side}; // TODO LEVEL_HEIGHT and othe rmap data into sth similar to renderer_data
-use crate::render::raycast::{RayHit, RayHitOrderer, intersect};
-use crate::render::renderer_init::RendererData;
-use crate::{SCREEN_HEIGHT, SCREEN_WIDTH}; // TODO fully move this into renderer_data (currently problem because arraysize wants constant, typing)
+use crate::game::map::{Point, Shape, ShapeType, Surfaces};
+use crate::render::hud::draw_debug_overlay;
+use crate::render::raycast::RayHit;
+use crate::render::renderer_init::{RendererData, ViewMode};
+use crate::render::sprites::draw_sprites;
+use crate::render::topdown_view::{Viewport, draw_topdown};
+
+const MINIMAP_MARGIN: usize = 10; // pixels between minimap and screen edge
+const MAX_VIEW_DISTANCE: f64 = 10_000.0; // how far floors reach when the ray hits no wall at all
+const COLUMNS_PER_BATCH: usize = 16; // how many columns a render thread takes at once
+const LIGHT_SAMPLE_OFFSET: f64 = 0.01; // how far in front of a side the light of the region it faces is looked up
 
 pub fn draw(buffer: &mut [u32], renderer_data: &RendererData, game: &Game) {
     //write grey plane as background to overwrite past frames
     for px in buffer.iter_mut() {
-        *px = renderer_data.background_color;
+        *px = game.map.metadata.background_color;
+    }
+    match renderer_data.view_mode {
+        ViewMode::Camera => {
+            //go through FOV in small steps, for each draw the corresponding line based on distance in 2.5 view
+            draw_camera_view(buffer, &renderer_data, game);
+            if renderer_data.show_minimap {
+                draw_topdown(buffer, renderer_data, game, minimap_viewport(renderer_data));
+            }
+        }
+        ViewMode::TopDown => {
+            draw_topdown(
+                buffer,
+                renderer_data,
+                game,
+                Viewport::full_screen(renderer_data),
+            );
+        }
+    }
+    if renderer_data.show_debug {
+        draw_debug_overlay(buffer, renderer_data, game);
+    }
+}
+
+// top right corner, a quarter of the screen in each direction
+pub fn minimap_viewport(renderer_data: &RendererData) -> Viewport {
+    let width = renderer_data.screen_width / 4;
+    let height = renderer_data.screen_height / 4;
+    Viewport {
+        x: renderer_data
+            .screen_width
+            .saturating_sub(width + MINIMAP_MARGIN),
+        y: MINIMAP_MARGIN,
+        width,
+        height,
+        stride: renderer_data.screen_width,
     }
-    //draw the top down map
-    // draw_map(buffer, game).unwrap();
-    //go through FOV in small steps, for each draw ray in top down view and corresponding line based on distance in 2.5 view
-    draw_camera_view(buffer, &renderer_data, game);
-    //draw player with his looking angle
-    // draw_player(buffer, game);
-    //draw grid of reference points spaced each 50 pixels for debugging
-    draw_reference_points(buffer);
 }
 
 fn draw_camera_view(buffer: &mut [u32], renderer_data: &RendererData, game: &Game) {
-    for x in 0..SCREEN_WIDTH {
-        let pixel_distance_from_screen_middle: f64 = x as f64 - SCREEN_WIDTH as f64 / 2.0;
+    // the view is rendered at the internal resolution and scaled up to the frame at the end
+    let view = renderer_data.scaled_view();
+    // columns only read the game, so they are rendered into their own slots and copied into the frame afterwards;
+    // the slots lie one after another in two buffers, column by column
+    let (width, height) = (view.screen_width, view.screen_height);
+    let mut pixels = vec![0; width * height];
+    let mut depth = vec![f64::INFINITY; width * height];
+    let mut columns: Vec<Column> = pixels
+        .chunks_mut(height)
+        .zip(depth.chunks_mut(height))
+        .map(|(pixels, depth)| Column { pixels, depth })
+        .collect();
+    // the lights change over time, but not within a frame, so the columns only look their levels up
+    let light_levels: Vec<f64> = game
+        .map
+        .lights
+        .iter()
+        .map(|light| light.level_at(game.time))
+        .collect();
+    let threads = view.threads.clamp(1, width);
+    if threads == 1 {
+        draw_columns(&mut columns, 0, &view, game, &light_levels);
+    } else {
+        // threads take small batches of neighbouring columns until none are left, so a thread that got
+        // the cheap columns (sky, far away walls) helps with the expensive ones
+        let batches = Mutex::new(columns.chunks_mut(COLUMNS_PER_BATCH).enumerate());
+        thread::scope(|scope| {
+            for _ in 0..threads {
+                scope.spawn(|| {
+                    loop {
+                        let Some((index, batch)) = batches.lock().unwrap().next() else {
+                            break;
+                        };
+                        draw_columns(batch, index * COLUMNS_PER_BATCH, &view, game, &light_levels);
+                    }
+                });
+            }
+        });
+    }
+
+    // sprites go over the finished columns, the depth buffer hides them behind nearer walls
+    draw_sprites(&mut columns, &view, game);
+
+    //draw columns into buffer, nearest neighbour: every frame pixel shows the view pixel it falls on
+    let (frame_width, frame_height) = (renderer_data.screen_width, renderer_data.screen_height);
+    for frame_y in 0..frame_height {
+        // read columns in reverse vertical order; that way other functions can pretend y=0 is botto of screen
+        let y = (frame_height - (frame_y + 1)) * height / frame_height;
+        let row = &mut buffer[frame_y * frame_width..(frame_y + 1) * frame_width];
+        for (frame_x, pixel) in row.iter_mut().enumerate() {
+            let x = frame_x * width / frame_width;
+            *pixel = pixels[x * height + y];
+        }
+    }
+}
+
+// first_x is the screen column of columns[0]; light_levels has the current level of every light of the map
+fn draw_columns(
+    columns: &mut [Column],
+    first_x: usize,
+    renderer_data: &RendererData,
+    game: &Game,
+    light_levels: &[f64],
+) {
+    for (offset, column) in columns.iter_mut().enumerate() {
+        let pixel_distance_from_screen_middle: f64 =
+            (first_x + offset) as f64 - renderer_data.screen_width_as_f64 / 2.0;
         let angle_relative_to_player: f64 = (pixel_distance_from_screen_middle
             / renderer_data.projection_plane_distance as f64)
             .atan();
 
-        let column: [u32; SCREEN_HEIGHT] = draw_column(
+        draw_column(
+            column,
             game,
             renderer_data,
             angle_relative_to_player,
             game.player.view_angle,
+            light_levels,
         );
+    }
+}
 
-        //draw column into buffer
-        for y in 0..column.len() {
-            // read columns in reverse vertical order; that way other functions can pretend y=0 is botto of screen
-            buffer[(SCREEN_HEIGHT - (y + 1)) * SCREEN_WIDTH + x] = column[y];
+// one thing drawn into a column; parts are painted back to front so nearer ones cover farther ones
+enum ColumnPart<'a> {
+    Side(RayHit<'a>),
+    // floor and ceiling between two distances along the ray
+    Surfaces {
+        near: f64,
+        far: f64,
+        surfaces: Surfaces,
+    },
+    // top of a block between where the ray enters and leaves it
+    BlockTop {
+        near: f64,
+        far: f64,
+        block: &'a Shape,
+    },
+    // vertical face where the floor or ceiling height changes between two sectors
+    Step {
+        distance: f64,
+        bottom: f64,
+        top: f64,
+        color: u32,
+        light: usize, // index into Map::lights
+    },
+}
+
+impl ColumnPart<'_> {
+    fn distance(&self) -> f64 {
+        match self {
+            ColumnPart::Side(rh) => rh.distance,
+            ColumnPart::Surfaces { near, .. } | ColumnPart::BlockTop { near, .. } => *near,
+            ColumnPart::Step { distance, .. } => *distance,
         }
     }
+
+    // a floor starting exactly where a side stands lies behind that side, so it has to be painted first
+    fn paint_order(&self) -> u8 {
+        match self {
+            ColumnPart::Surfaces { .. } => 0,
+            ColumnPart::Side(_) | ColumnPart::BlockTop { .. } | ColumnPart::Step { .. } => 1,
+        }
+    }
+}
+
+// one screen column, bottom row first; both slices are as long as the screen is high
+pub(super) struct Column<'a> {
+    pub(super) pixels: &'a mut [u32],
+    // distance along the view direction (not along the ray) of what each pixel shows, infinite for the background
+    pub(super) depth: &'a mut [f64],
+}
+
+impl Column<'_> {
+    pub(super) fn paint(&mut self, row: usize, color: u32, depth: f64) {
+        self.pixels[row] = color;
+        self.depth[row] = depth;
+    }
 }
 
 fn draw_column(
+    column: &mut Column,
     game: &Game,
     renderer_data: &RendererData,
     angle_relative_to_player: f64,
     player_angle: f64,
-) -> [u32; SCREEN_HEIGHT] {
-    // // let mut side1 : Side;
-    //     let shape_content: Shape = (*shape).clone()?; // TODO remove necessity for clone() maybe?
-    //     let mut intersects = false;
-    //     for side in shape_content.sides {
-    //         if intersect(point, 0.0, side).is_some() {
-    //             intersects=!intersects;
-    //         }
-    //     }
-    //     Some(intersects)
-    let mut column: [u32; SCREEN_HEIGHT] = [renderer_data.background_color; SCREEN_HEIGHT]; // initialized with default value
+    light_levels: &[f64],
+) {
+    // initialized with default value
+    column.pixels.fill(game.map.metadata.background_color);
+    column.depth.fill(f64::INFINITY);
 
     let ray_angle = player_angle + angle_relative_to_player;
-
-    // TODO side masterlist: run through all in one, maintain not-behind-closest-wall functionality
-    //find closest wall
+    let player_position = Point {
+        x: game.player.position_x,
+        y: game.player.position_y,
+    };
+
+    // the grid hands out hits nearest first, so everything up to the first wall is all that can be seen
+    let mut parts: Vec<ColumnPart> = Vec::new();
+    let mut sector_crossings: Vec<f64> = Vec::new();
+    let mut block_crossings: Vec<(usize, f64)> = Vec::new(); // block index and distance
     let mut closest_wall_hit: Option<RayHit> = None;
-    for w in &game.map.walls {
-        for s in &w.sides {
-            let intersection = intersect(
-                Point {
-                    x: game.player.position_x,
-                    y: game.player.position_y,
-                },
-                ray_angle,
-                s.clone(),
-            );
-            if let Some(intersection) = intersection {
-                if let Some(wall_hit) = &closest_wall_hit
-                    && wall_hit.distance < intersection.distance
-                {
-                    continue;
-                }
-                closest_wall_hit = Some(intersection);
+    game.map.side_grid.cast_ray(
+        game.map.shapes(),
+        player_position,
+        ray_angle,
+        MAX_VIEW_DISTANCE,
+        |side_ref, hit| match side_ref.shape_type {
+            ShapeType::Wall => {
+                closest_wall_hit = Some(hit);
+                false
+            }
+            ShapeType::Block => {
+                block_crossings.push((side_ref.shape_index, hit.distance));
+                parts.push(ColumnPart::Side(hit));
+                true
+            }
+            ShapeType::Sector => {
+                sector_crossings.push(hit.distance);
+                true
             }
+        },
+    );
+    // nothing behind the closest wall is visible
+    let view_distance = closest_wall_hit
+        .as_ref()
+        .map_or(MAX_VIEW_DISTANCE, |wall_hit| wall_hit.distance);
+
+    // floors and ceilings change where the ray crosses into another sector and are cut wherever a side stands,
+    // so that every piece is either completely in front of or behind every side
+    let mut cuts: Vec<f64> = parts.iter().map(ColumnPart::distance).collect();
+    cuts.extend(sector_crossings);
+    cuts.push(view_distance);
+    cuts.sort_by(f64::total_cmp);
+
+    let mut near = 0.0;
+    let mut previous_surfaces: Option<Surfaces> = None;
+    for far in cuts {
+        if far - near < f64::EPSILON {
+            continue;
         }
+        let middle_distance = (near + far) / 2.0;
+        let surfaces = game.map.surfaces_at(Point {
+            x: player_position.x + ray_angle.cos() * middle_distance,
+            y: player_position.y + ray_angle.sin() * middle_distance,
+        });
+        if let Some(previous) = previous_surfaces {
+            parts.extend(steps_between(&previous, &surfaces, near));
+        }
+        parts.push(ColumnPart::Surfaces {
+            near,
+            far,
+            surfaces,
+        });
+        previous_surfaces = Some(surfaces);
+        near = far;
     }
 
-    let mut rayhits_ordered: BinaryHeap<RayHitOrderer> = BinaryHeap::new();
-    for b in &game.map.blocks {
-        for s in &b.sides {
-            let intersection = intersect(
-                Point {
-                    x: game.player.position_x,
-                    y: game.player.position_y,
-                },
-                ray_angle,
-                s.clone(),
-            );
-            if let Some(intersection) = intersection {
-                if let Some(wall_hit) = &closest_wall_hit
-                    && wall_hit.distance < intersection.distance
-                {
-                    continue;
-                }
-                rayhits_ordered.push(RayHitOrderer { rh: intersection });
+    parts.extend(block_tops(
+        game,
+        player_position,
+        &block_crossings,
+        view_distance,
+    ));
+
+    if let Some(wall_hit) = closest_wall_hit {
+        parts.push(ColumnPart::Side(wall_hit));
+    }
+
+    parts.sort_by(|a, b| {
+        b.distance()
+            .total_cmp(&a.distance())
+            .then(a.paint_order().cmp(&b.paint_order()))
+    });
+
+    let projection = ColumnProjection {
+        horizon: renderer_data.horizon(game.player.pitch),
+        vertical_scale_coefficient: renderer_data.vertical_scale_coefficient,
+        eye_height: game.player.view_height,
+        fisheye_correction: angle_relative_to_player.cos(), // cos for anti-fisheye effect
+        distance_darkness_coefficient: game.map.metadata.distance_darkness_coefficient,
+    };
+
+    // draw the parts over one another
+    for part in parts {
+        match part {
+            ColumnPart::Side(rh) => {
+                let light = light_levels[side_light(game, &rh, player_position)];
+                draw_side(column, &projection, game, &rh, light);
+            }
+            ColumnPart::Surfaces {
+                near,
+                far,
+                surfaces,
+            } => {
+                let light = light_levels[surfaces.light];
+                draw_surfaces(column, &projection, near, far, &surfaces, light);
+            }
+            ColumnPart::BlockTop { near, far, block } => {
+                let texture_size = game.map.metadata.texture_size;
+                let texture = block.top_texture.and_then(|t| game.map.textures.get(t));
+                let color = block
+                    .top_color
+                    .unwrap_or(game.map.metadata.block_default_color);
+                // without a light of its own the top is lit like the region it stands in
+                let light = block.light.unwrap_or_else(|| {
+                    game.map
+                        .surfaces_at(Point {
+                            x: player_position.x + ray_angle.cos() * near,
+                            y: player_position.y + ray_angle.sin() * near,
+                        })
+                        .light
+                });
+                let light = light_levels[light];
+                // every side of a block has the same height
+                draw_horizontal_span(
+                    column,
+                    &projection,
+                    near,
+                    far,
+                    block.sides[0].height,
+                    light,
+                    |distance| match texture {
+                        // the texture lies on the world's x/y grid, so it lines up across the whole top
+                        Some(texture) => {
+                            let position = Point {
+                                x: player_position.x + ray_angle.cos() * distance,
+                                y: player_position.y + ray_angle.sin() * distance,
+                            };
+                            texture.sample(position.x / texture_size, position.y / texture_size)
+                        }
+                        None => color,
+                    },
+                );
+            }
+            ColumnPart::Step {
+                distance,
+                bottom,
+                top,
+                color,
+                light,
+            } => {
+                let brightness = projection.brightness(distance, 1.0, light_levels[light]);
+                draw_vertical_slice(column, &projection, distance, bottom, top, |_| {
+                    shade(color, brightness)
+                });
             }
         }
     }
-    if let Some(wall_hit) = closest_wall_hit {
-        rayhits_ordered.push(RayHitOrderer { rh: wall_hit });
+}
+
+// everything needed to turn world heights at a distance along the ray into rows of the column
+struct ColumnProjection {
+    horizon: f64,
+    vertical_scale_coefficient: f64,
+    eye_height: f64,
+    fisheye_correction: f64,
+    distance_darkness_coefficient: f64,
+}
+
+impl ColumnProjection {
+    // row (counted from the bottom of the screen) at which a point at world height z appears
+    fn row_of(&self, distance: f64, z: f64) -> f64 {
+        self.horizon
+            + (z - self.eye_height) / (distance * self.fisheye_correction)
+                * self.vertical_scale_coefficient
     }
 
-    if rayhits_ordered.is_empty() {
-        return [renderer_data.background_color; SCREEN_HEIGHT]; // default return value: empty column
+    // distance along the ray at which the horizontal plane at height z shows up in the given row
+    fn distance_of(&self, row: f64, z: f64) -> f64 {
+        (z - self.eye_height) * self.vertical_scale_coefficient
+            / ((row - self.horizon) * self.fisheye_correction)
     }
 
-    // draw the sides for each ray hit over one another
-    // TODO remove need for type conversions
-    while !rayhits_ordered.is_empty() {
-        if let Some(rh_ordering) = rayhits_ordered.pop() {
-            let rh: RayHit = rh_ordering.rh;
+    // light is the level of the light on the surface at this moment
+    fn brightness(&self, distance: f64, facing: f64, light: f64) -> f64 {
+        brightness(distance, facing, self.distance_darkness_coefficient) * light
+    }
+}
 
-            let color = match rh.side.side_type {
-                ShapeType::Wall => renderer_data.wall_default_color,
-                ShapeType::Block => renderer_data.block_default_color,
-            };
+// facing is the cosine of the angle between the lit surface and the world's x axis, 1 for floors and sprites
+pub(super) fn brightness(distance: f64, facing: f64, distance_darkness_coefficient: f64) -> f64 {
+    (facing * 0.5 / (distance * distance_darkness_coefficient) + 0.5).clamp(0.2, 1.0)
+}
 
-            let normalized_distance_to_side = rh.distance * angle_relative_to_player.cos(); // cos for anti-fisheye effect
-
-            let side_onscreen_height = ((rh.side.height / normalized_distance_to_side)
-                * renderer_data.vertical_scale_coefficient)
-                as isize; // must be addable to bottom_onscreen
-
-            //find out what ray we are currently casting to know where on the x axis to draw the line in the 2.5 view
-            //let center_x = WIDTH as f64 * 0.5;
-            //let proj_dist = center_x / (FOV * 0.5).tan();
-            //let x             let side_bottom_onscreen = (SCREEN_HEIGHT as f64 / 2.0)
-            let side_bottom_onscreen: isize = ((renderer_data.screen_height_as_f64 / 2.0)
-                - (game.player.view_height / normalized_distance_to_side)
-                    * renderer_data.vertical_scale_coefficient)
-                as isize; // must be able to be negative
-
-            for onscreen_y_isize in side_bottom_onscreen 
-                ..(side_bottom_onscreen + side_onscreen_height) 
-            {
-                let onscreen_y = onscreen_y_isize as usize;
-
-                if onscreen_y >= SCREEN_HEIGHT {
-                    continue;
-                }
-
-                let brightness = (rh.side.angle_in_world.cos() * 0.5
-                    / (rh.distance as f64 * renderer_data.distance_darkness_coefficient)
-                    + 0.5)
-                    .clamp(0.2, 1.0);
-                // 2. Extract channels
-                let a = (color >> 24) & 0xFF;
-                let r = (color >> 16) & 0xFF;
-                let g = (color >> 8) & 0xFF;
-                let b = color & 0xFF;
-
-                // 3. Scale each channel
-                let r = (r as f64 * brightness) as u32;
-                let g = (g as f64 * brightness) as u32;
-                let b = (b as f64 * brightness) as u32;
-
-                // 4. Repack
-                column[onscreen_y] = (a << 24) | (r << 16) | (g << 8) | b;
+// pairs up where the ray enters and leaves each block it crosses; only tops below the eye can be seen
+fn block_tops<'a>(
+    game: &'a Game,
+    player_position: Point,
+    block_crossings: &[(usize, f64)],
+    view_distance: f64,
+) -> Vec<ColumnPart<'a>> {
+    let mut tops = Vec::new();
+    let mut blocks: Vec<usize> = block_crossings.iter().map(|&(block, _)| block).collect();
+    blocks.sort_unstable();
+    blocks.dedup();
+    for index in blocks {
+        let block = &game.map.blocks[index];
+        if block.sides[0].height >= game.player.view_height {
+            continue;
+        }
+        // standing above the block, the ray starts out inside of it
+        let mut entered_at = block.contains(player_position).then_some(0.0);
+        for &(_, distance) in block_crossings.iter().filter(|&&(b, _)| b == index) {
+            match entered_at.take() {
+                Some(near) => tops.push(ColumnPart::BlockTop {
+                    near,
+                    far: distance,
+                    block,
+                }),
+                None => entered_at = Some(distance),
             }
         }
+        if let Some(near) = entered_at {
+            tops.push(ColumnPart::BlockTop {
+                near,
+                far: view_distance,
+                block,
+            });
+        }
     }
+    tops
+}
+
+fn steps_between(near: &Surfaces, far: &Surfaces, distance: f64) -> Vec<ColumnPart<'static>> {
+    let mut steps = Vec::new();
+    if near.floor_height != far.floor_height {
+        steps.push(ColumnPart::Step {
+            distance,
+            bottom: near.floor_height.min(far.floor_height),
+            top: near.floor_height.max(far.floor_height),
+            color: far.floor_color,
+            light: far.light,
+        });
+    }
+    if let (Some(_), Some(ceiling_color)) = (near.ceiling_color, far.ceiling_color)
+        && near.ceiling_height != far.ceiling_height
+    {
+        steps.push(ColumnPart::Step {
+            distance,
+            bottom: near.ceiling_height.min(far.ceiling_height),
+            top: near.ceiling_height.max(far.ceiling_height),
+            color: ceiling_color,
+            light: far.light,
+        });
+    }
+    steps
+}
+
+// index into Map::lights; a side without a light of its own is lit like the region it is seen from
+fn side_light(game: &Game, rh: &RayHit, player_position: Point) -> usize {
+    rh.side.light.unwrap_or_else(|| {
+        let towards_player = player_position - rh.position;
+        let length = towards_player.length();
+        let in_front = if length > LIGHT_SAMPLE_OFFSET {
+            rh.position + towards_player * (LIGHT_SAMPLE_OFFSET / length)
+        } else {
+            player_position
+        };
+        game.map.surfaces_at(in_front).light
+    })
+}
 
-    //find the point the ray intersects the wall
-    // let ray_dx = ray_angle.cos();
-    // let ray_dy = ray_angle.sin();
-
-    // let wall_point = Point {
-    //     x: game.player.position_x + ray_dx * distance_to_wall,
-    //     y: game.player.position_y + ray_dy * distance_to_wall,
-    // };
-    //draw the line of this ray up to its intersect
-    //draw_line(buffer, game.player.position_x as usize, game.player.position_y as usize, wall_point.x as usize, wall_point.y as usize, 0xff0000);
-    return column;
+// light is the current level of the side's light
+fn draw_side(
+    column: &mut Column,
+    projection: &ColumnProjection,
+    game: &Game,
+    rh: &RayHit,
+    light: f64,
+) {
+    let default_color = match rh.side.side_type {
+        ShapeType::Wall | ShapeType::Sector => game.map.metadata.wall_default_color,
+        ShapeType::Block => game.map.metadata.block_default_color,
+    };
+    let texture = rh.side.texture.and_then(|t| game.map.textures.get(t));
+    let brightness = projection.brightness(rh.distance, rh.side.angle_in_world.cos(), light);
+
+    // texture coordinates count in texture repetitions, so long or tall sides tile instead of stretching
+    let texture_size = game.map.metadata.texture_size;
+    let texture_u = rh.proportion_along_side * rh.side.length / texture_size;
+
+    draw_vertical_slice(
+        column,
+        projection,
+        rh.distance,
+        0.0,
+        rh.side.height,
+        |height_on_side| {
+            let color = match texture {
+                Some(texture) => texture.sample(texture_u, height_on_side / texture_size),
+                None => default_color,
+            };
+            shade(color, brightness)
+        },
+    );
+}
+
+// draws something standing upright at the given distance from world height bottom to top;
+// pixel_color gets the height above bottom that the pixel shows
+fn draw_vertical_slice(
+    column: &mut Column,
+    projection: &ColumnProjection,
+    distance: f64,
+    bottom: f64,
+    top: f64,
+    pixel_color: impl Fn(f64) -> u32,
+) {
+    let bottom_row = projection.row_of(distance, bottom);
+    let top_row = projection.row_of(distance, top);
+    let world_height_per_row = (top - bottom) / (top_row - bottom_row);
+
+    let depth = distance * projection.fisheye_correction;
+
+    let first_row = bottom_row.round().max(0.0) as usize;
+    let end_row = (top_row.round().max(0.0) as usize).min(column.pixels.len());
+    for row in first_row..end_row {
+        let height_on_slice = (row as f64 + 0.5 - bottom_row) * world_height_per_row;
+        column.paint(row, pixel_color(height_on_slice), depth);
+    }
+}
+
+// floor below and ceiling above the eye between the two distances
+fn draw_surfaces(
+    column: &mut Column,
+    projection: &ColumnProjection,
+    near: f64,
+    far: f64,
+    surfaces: &Surfaces,
+    light: f64,
+) {
+    if surfaces.floor_height < projection.eye_height {
+        // the floor goes from near at the bottom up towards the horizon
+        draw_horizontal_span(
+            column,
+            projection,
+            near,
+            far,
+            surfaces.floor_height,
+            light,
+            |_| surfaces.floor_color,
+        );
+    }
+    if let Some(ceiling_color) = surfaces.ceiling_color
+        && surfaces.ceiling_height > projection.eye_height
+    {
+        draw_horizontal_span(
+            column,
+            projection,
+            near,
+            far,
+            surfaces.ceiling_height,
+            light,
+            |_| ceiling_color,
+        );
+    }
+}
+
+// a horizontal plane at height z seen between the two distances; shaded per row by the distance it shows.
+// pixel_color gets the distance along the ray that the pixel shows
+fn draw_horizontal_span(
+    column: &mut Column,
+    projection: &ColumnProjection,
+    near: f64,
+    far: f64,
+    z: f64,
+    light: f64,
+    pixel_color: impl Fn(f64) -> u32,
+) {
+    // near can be 0 (standing on the span), which projects to infinity
+    let near_row = projection.row_of(near.max(f64::EPSILON), z);
+    let far_row = projection.row_of(far, z);
+    let (low_row, high_row) = if near_row < far_row {
+        (near_row, far_row)
+    } else {
+        (far_row, near_row)
+    };
+
+    let first_row = low_row.round().max(0.0) as usize;
+    let end_row = (high_row.round().max(0.0) as usize).min(column.pixels.len());
+    for row in first_row..end_row {
+        let depth = projection.distance_of(row as f64 + 0.5, z);
+        let distance = depth / projection.fisheye_correction;
+        column.paint(
+            row,
+            shade(
+                pixel_color(distance),
+                projection.brightness(distance, 1.0, light),
+            ),
+            depth,
+        );
+    }
 }
 
 // fn draw_dimensional_cast(
@@ -225,133 +654,67 @@ fn draw_column(
 //     }
 // }
 
-//draw refernce points spaced 50 pixels apart for debugging
-fn draw_reference_points(buffer: &mut [u32]) {
-    for x in 0..SCREEN_WIDTH {
-        for y in 0..SCREEN_HEIGHT {
-            if x % 50 == 0 && y % 50 == 0 {
-                buffer[y * SCREEN_WIDTH + x] = 0xff0000;
-            }
-        }
-    }
+// scales the color channels by brightness, keeping alpha
+pub(super) fn shade(color: u32, brightness: f64) -> u32 {
+    // 1. Extract channels
+    let a = (color >> 24) & 0xFF;
+    let r = (color >> 16) & 0xFF;
+    let g = (color >> 8) & 0xFF;
+    let b = color & 0xFF;
+
+    // 2. Scale each channel
+    let r = (r as f64 * brightness) as u32;
+    let g = (g as f64 * brightness) as u32;
+    let b = (b as f64 * brightness) as u32;
+
+    // 3. Repack
+    (a << 24) | (r << 16) | (g << 8) | b
 }
 
-//save all points from the screen that are in the polygon of the map boarder and note that map is loaded now
-////! right now load map is working not as intended in the game, because right now it loads the init of map, so right now it just means that we init the map, later however it will indicate what map was loaded into the map boarder
-// fn load_map (game: & mut Game) -> Result<(),Box<dyn  std::error::Error>>{
-//     for x in 0..WIDTH {
-//         for y in 0..HEIGHT{
-//             if point_in_polygon(&game.map.walls, Point { x: x as f64, y: y as f64 }){
-//                 game.map.points_in_border.push(Point{x: x as f64, y: y as f64});
-//             }
-//         }
-//     }
-//     game.map.loaded_map=1;
-//     Ok(())
-// }
-
-//draw the top down view of the map init
-// fn draw_map (buffer: &mut [u32], game: &Game) -> Result<(),Box<dyn  std::error::Error>>{
-//     for points in game.map.points_in_border.clone() {
-//             if point_in_polygon(&game.map.walls, Point { x: points.x as f64, y: points.y as f64 }){
-//                 buffer[points.y as usize*WIDTH+points.x as usize] = 0x00ff00;
-//         }
-//         //draw object
-//         if point_in_polygon(&game.map.blocks.get(0).unwrap(), Point { x: points.x as f64, y: points.y as f64 }){
-//                 buffer[points.y as usize*WIDTH+points.x as usize] = 0x0000ff;
-//         }
-//     }
-//     Ok(())
-// }
-
-// fn draw_player (buffer: &mut [u32], game: &Game) {
-//     //make player thicccker but have to check for out of bounds
-//     let x = game.player.position_x as isize;
-//     let y = game.player.position_y as isize;
-
-//     for dx in -1..=1 {
-//         for dy in -1..=1 {
-//             let px = x + dx;
-//             let py = y + dy;
-
-//             if px >= 0 && px < WIDTH as isize && py >= 0 && py < HEIGHT as isize {
-//                 let ux = px as usize;
-//                 let uy = py as usize;
-//                 let index = uy * WIDTH + ux;
-//                 buffer[index] = 0xff0000;
-//             }
-//         }
-//     }
-//     //draw direction of player looking as a small line
-//     let x1f = game.player.position_x+game.player.velocity_x*5.0;
-//     let y1f = game.player.position_y+game.player.velocity_y*5.0;
-
-//     let x1 = x1f.clamp(0.0, (WIDTH - 1) as f64) as usize;
-//     let y1 = y1f.clamp(0.0, (HEIGHT - 1) as f64) as usize;
-//     let x0 = game.player.position_x.clamp(0.0, (WIDTH-1) as f64) as usize;
-//     let y0 = game.player.position_y.clamp(0.0, (HEIGHT-1) as f64) as usize;
-
-//     draw_line(buffer, x0, y0, x1, y1, 0x00ffff);
-// }
-
-//
-
-//draw the vertical line for the ray that renders the the 2.5 view
-
-////! this func is a random chatgbt function, rewrite if we want to use it in the final code
-fn draw_line(buffer: &mut [u32], x0: usize, y0: usize, x1: usize, y1: usize, color: u32) {
-    // Convert to signed for math (avoids underflow)
-    let mut x0 = x0 as isize;
-    let mut y0 = y0 as isize;
-    let x1 = x1 as isize;
-    let y1 = y1 as isize;
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::render::renderer_init::render_init;
 
-    let dx = (x1 - x0).abs();
-    let sx = if x0 < x1 { 1 } else { -1 };
-    let dy = -(y1 - y0).abs();
-    let sy = if y0 < y1 { 1 } else { -1 };
-    let mut err = dx + dy;
+    const MAP: &str = "assets/maps/default.map";
 
-    loop {
-        // Only draw inside the screen
-        if x0 >= 0 && x0 < SCREEN_WIDTH as isize && y0 >= 0 && y0 < SCREEN_HEIGHT as isize {
-            buffer[y0 as usize * SCREEN_WIDTH + x0 as usize] = color;
-        }
+    fn frame(renderer_data: &RendererData, game: &Game) -> Vec<u32> {
+        let mut buffer = vec![0; renderer_data.screen_width * renderer_data.screen_height];
+        draw(&mut buffer, renderer_data, game);
+        buffer
+    }
 
-        if x0 == x1 && y0 == y1 {
-            break;
+    #[test]
+    fn threads_render_the_same_frame_as_one() {
+        let game = Game::new(MAP).unwrap();
+        let mut renderer_data = render_init(320, 200, 90f64.to_radians());
+        renderer_data.threads = 1;
+        let expected = frame(&renderer_data, &game);
+        // 3 threads don't divide the batches evenly
+        for threads in [2, 3, 8] {
+            renderer_data.threads = threads;
+            assert!(
+                frame(&renderer_data, &game) == expected,
+                "{threads} threads"
+            );
         }
+    }
 
-        let e2 = 2 * err;
-
-        if e2 >= dy {
-            err += dy;
-            x0 += sx;
-        }
-        if e2 <= dx {
-            err += dx;
-            y0 += sy;
+    #[test]
+    fn render_scale_repeats_every_view_pixel() {
+        let game = Game::new(MAP).unwrap();
+        let view = render_init(160, 100, 90f64.to_radians());
+        let small = frame(&view, &game);
+        let mut renderer_data = render_init(320, 200, 90f64.to_radians());
+        renderer_data.render_scale = 0.5;
+        let large = frame(&renderer_data, &game);
+        for (y, row) in large.chunks_exact(320).enumerate() {
+            for (x, &pixel) in row.iter().enumerate() {
+                assert_eq!(pixel, small[y / 2 * 160 + x / 2], "({x}, {y})");
+            }
         }
     }
-}
-
-// fn point_in_polygon (shape: &Option<Shape>, point: Point) -> Option<bool> {
-//     // let mut side1 : Side;
-//     let shape_content: Shape = (*shape).clone()?; // TODO remove necessity for clone() maybe?
-//     let mut intersects = false;
-//     for side in shape_content.sides {
-//         if intersect(point, 0.0, side).is_some() {
-//             intersects=!intersects;
-//         }
-//     }
-//     Some(intersects)
-// }
-
-#[cfg(test)]
-mod test {
-    use std::vec;
 
-    // use super::*;
     // #[test]
     // fn test_intersect() {
     //     let ray_origin1 = Point { x: 50.0, y: 200.0 };
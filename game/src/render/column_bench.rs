@@ -0,0 +1,153 @@
+// compares finding the hits of a column by testing every side with walking the side grid, on a map with thousands of sides;
+// also times whole camera frames with one and with all render threads;
+// run `cargo test --release column_bench -- --ignored --nocapture` to see the numbers
+use std::time::{Duration, Instant};
+
+use crate::game::Game;
+use crate::game::map::{MapContents, MapMetadata, Point, ShapeType};
+use crate::game::test_maps;
+use crate::render::raycast::intersect;
+use crate::render::{RendererData, draw, render_init};
+use crate::{HORIZONTAL_FOV, SCREEN_HEIGHT, SCREEN_WIDTH};
+
+const ROOM_SIZE: f64 = 2_000.0;
+const PILLARS_PER_ROW: usize = 40; // 40 * 40 pillars with 4 sides each
+const PILLAR_SIZE: f64 = 8.0;
+const FRAMES: usize = 20;
+
+// square room filled with a regular field of pillars, the player stands in a gap in the middle
+fn pillar_field() -> Game {
+    let metadata = MapMetadata {
+        player_start: Point {
+            x: ROOM_SIZE / 2.0,
+            y: ROOM_SIZE / 2.0,
+        },
+        ..MapMetadata::default()
+    };
+
+    let spacing = ROOM_SIZE / (PILLARS_PER_ROW + 1) as f64;
+    let mut blocks = Vec::new();
+    for row in 1..=PILLARS_PER_ROW {
+        for column in 1..=PILLARS_PER_ROW {
+            let corner = Point {
+                x: column as f64 * spacing - PILLAR_SIZE / 2.0,
+                y: row as f64 * spacing - PILLAR_SIZE / 2.0,
+            };
+            let far_corner = Point {
+                x: corner.x + PILLAR_SIZE,
+                y: corner.y + PILLAR_SIZE,
+            };
+            blocks.push(test_maps::rectangle(
+                corner,
+                far_corner,
+                ShapeType::Block,
+                15.0,
+            ));
+        }
+    }
+
+    let contents = MapContents {
+        blocks,
+        ..MapContents::default()
+    };
+    Game::from_map(test_maps::room(ROOM_SIZE, metadata, contents))
+}
+
+fn column_angles(projection_plane_distance: f64, view_angle: f64) -> impl Iterator<Item = f64> {
+    (0..SCREEN_WIDTH).map(move |x| {
+        let pixel_distance_from_screen_middle = x as f64 - SCREEN_WIDTH as f64 / 2.0;
+        view_angle + (pixel_distance_from_screen_middle / projection_plane_distance).atan()
+    })
+}
+
+fn per_column(elapsed: Duration) -> Duration {
+    elapsed / (FRAMES * SCREEN_WIDTH) as u32
+}
+
+#[test]
+#[ignore = "benchmark, run with --release --ignored --nocapture"]
+fn column_bench() {
+    let mut game = pillar_field();
+    let renderer_data = render_init(SCREEN_WIDTH, SCREEN_HEIGHT, HORIZONTAL_FOV);
+    let shapes = game.map.shapes();
+    let origin = Point {
+        x: game.player.position_x,
+        y: game.player.position_y,
+    };
+    let side_count = game
+        .map
+        .walls
+        .iter()
+        .chain(&game.map.blocks)
+        .map(|s| s.sides.len())
+        .sum::<usize>();
+
+    // every side is tested and the hits are sorted, which is what draw_column did before the grid
+    let mut brute_force_hits = 0;
+    let start = Instant::now();
+    for frame in 0..FRAMES {
+        let view_angle = frame as f64 * 0.3;
+        for angle in column_angles(renderer_data.projection_plane_distance, view_angle) {
+            let mut hits: Vec<_> = game
+                .map
+                .walls
+                .iter()
+                .chain(&game.map.blocks)
+                .flat_map(|shape| &shape.sides)
+                .filter_map(|side| intersect(origin, angle, side))
+                .collect();
+            hits.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+            brute_force_hits += hits
+                .iter()
+                .take_while(|hit| hit.side.side_type != ShapeType::Wall)
+                .count();
+        }
+    }
+    let brute_force = start.elapsed();
+
+    let mut grid_hits = 0;
+    let start = Instant::now();
+    for frame in 0..FRAMES {
+        let view_angle = frame as f64 * 0.3;
+        for angle in column_angles(renderer_data.projection_plane_distance, view_angle) {
+            game.map
+                .side_grid
+                .cast_ray(shapes, origin, angle, f64::INFINITY, |side_ref, _| {
+                    if side_ref.shape_type == ShapeType::Wall {
+                        return false;
+                    }
+                    grid_hits += 1;
+                    true
+                });
+        }
+    }
+    let grid = start.elapsed();
+    assert_eq!(
+        brute_force_hits, grid_hits,
+        "both ways should find the same blocks"
+    );
+
+    let mut buffer = vec![0; SCREEN_WIDTH * SCREEN_HEIGHT];
+    let mut frame_time = |renderer_data: &RendererData| {
+        let start = Instant::now();
+        for frame in 0..FRAMES {
+            game.player.view_angle = frame as f64 * 0.3;
+            draw(&mut buffer, renderer_data, &game);
+        }
+        start.elapsed() / FRAMES as u32
+    };
+    let single_threaded = frame_time(&RendererData {
+        threads: 1,
+        ..render_init(SCREEN_WIDTH, SCREEN_HEIGHT, HORIZONTAL_FOV)
+    });
+    let multi_threaded = frame_time(&renderer_data);
+
+    println!("{side_count} sides, {SCREEN_WIDTH} columns, {FRAMES} frames");
+    println!("brute force: {:?} per column", per_column(brute_force));
+    println!("side grid:   {:?} per column", per_column(grid));
+    println!("full camera frame, 1 thread: {single_threaded:?}");
+    println!(
+        "full camera frame, {} threads: {multi_threaded:?}",
+        renderer_data.threads
+    );
+}
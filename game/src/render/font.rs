@@ -0,0 +1,141 @@
+pub const GLYPH_WIDTH: usize = 5;
+pub const GLYPH_HEIGHT: usize = 7;
+pub const LETTER_SPACING: usize = 1; // empty pixel columns between two characters
+pub const LINE_HEIGHT: usize = GLYPH_HEIGHT + 3;
+const SHADOW_COLOR: u32 = 0x000000;
+
+// 5x7 pixel font, drawn the way it looks on screen; lowercase letters are drawn as uppercase,
+// every other character without a glyph as '?'
+#[rustfmt::skip]
+const GLYPHS: &[(char, [&str; GLYPH_HEIGHT])] = &[
+    (' ', ["     ", "     ", "     ", "     ", "     ", "     ", "     "]),
+    ('!', ["  #  ", "  #  ", "  #  ", "  #  ", "  #  ", "     ", "  #  "]),
+    ('"', [" # # ", " # # ", "     ", "     ", "     ", "     ", "     "]),
+    ('#', [" # # ", " # # ", "#####", " # # ", "#####", " # # ", " # # "]),
+    ('%', ["##   ", "##  #", "   # ", "  #  ", " #   ", "#  ##", "   ##"]),
+    ('\'', ["  #  ", "  #  ", "     ", "     ", "     ", "     ", "     "]),
+    ('(', ["   # ", "  #  ", " #   ", " #   ", " #   ", "  #  ", "   # "]),
+    (')', [" #   ", "  #  ", "   # ", "   # ", "   # ", "  #  ", " #   "]),
+    ('*', ["     ", "  #  ", "# # #", " ### ", "# # #", "  #  ", "     "]),
+    ('+', ["     ", "  #  ", "  #  ", "#####", "  #  ", "  #  ", "     "]),
+    (',', ["     ", "     ", "     ", "     ", "  ## ", "   # ", "  #  "]),
+    ('-', ["     ", "     ", "     ", "#####", "     ", "     ", "     "]),
+    ('.', ["     ", "     ", "     ", "     ", "     ", " ##  ", " ##  "]),
+    ('/', ["     ", "    #", "   # ", "  #  ", " #   ", "#    ", "     "]),
+    ('0', [" ### ", "#   #", "#  ##", "# # #", "##  #", "#   #", " ### "]),
+    ('1', ["  #  ", " ##  ", "  #  ", "  #  ", "  #  ", "  #  ", " ### "]),
+    ('2', [" ### ", "#   #", "    #", "   # ", "  #  ", " #   ", "#####"]),
+    ('3', ["#####", "   # ", "  #  ", "   # ", "    #", "#   #", " ### "]),
+    ('4', ["   # ", "  ## ", " # # ", "#  # ", "#####", "   # ", "   # "]),
+    ('5', ["#####", "#    ", "#### ", "    #", "    #", "#   #", " ### "]),
+    ('6', ["  ## ", " #   ", "#    ", "#### ", "#   #", "#   #", " ### "]),
+    ('7', ["#####", "    #", "   # ", "  #  ", " #   ", " #   ", " #   "]),
+    ('8', [" ### ", "#   #", "#   #", " ### ", "#   #", "#   #", " ### "]),
+    ('9', [" ### ", "#   #", "#   #", " ####", "    #", "   # ", " ##  "]),
+    (':', ["     ", " ##  ", " ##  ", "     ", " ##  ", " ##  ", "     "]),
+    (';', ["     ", " ##  ", " ##  ", "     ", " ##  ", "  #  ", " #   "]),
+    ('<', ["   # ", "  #  ", " #   ", "#    ", " #   ", "  #  ", "   # "]),
+    ('=', ["     ", "     ", "#####", "     ", "#####", "     ", "     "]),
+    ('>', [" #   ", "  #  ", "   # ", "    #", "   # ", "  #  ", " #   "]),
+    ('?', [" ### ", "#   #", "    #", "   # ", "  #  ", "     ", "  #  "]),
+    ('A', [" ### ", "#   #", "#   #", "#####", "#   #", "#   #", "#   #"]),
+    ('B', ["#### ", "#   #", "#   #", "#### ", "#   #", "#   #", "#### "]),
+    ('C', [" ### ", "#   #", "#    ", "#    ", "#    ", "#   #", " ### "]),
+    ('D', ["###  ", "#  # ", "#   #", "#   #", "#   #", "#  # ", "###  "]),
+    ('E', ["#####", "#    ", "#    ", "#### ", "#    ", "#    ", "#####"]),
+    ('F', ["#####", "#    ", "#    ", "#### ", "#    ", "#    ", "#    "]),
+    ('G', [" ### ", "#   #", "#    ", "# ###", "#   #", "#   #", " ####"]),
+    ('H', ["#   #", "#   #", "#   #", "#####", "#   #", "#   #", "#   #"]),
+    ('I', [" ### ", "  #  ", "  #  ", "  #  ", "  #  ", "  #  ", " ### "]),
+    ('J', ["  ###", "   # ", "   # ", "   # ", "   # ", "#  # ", " ##  "]),
+    ('K', ["#   #", "#  # ", "# #  ", "##   ", "# #  ", "#  # ", "#   #"]),
+    ('L', ["#    ", "#    ", "#    ", "#    ", "#    ", "#    ", "#####"]),
+    ('M', ["#   #", "## ##", "# # #", "# # #", "#   #", "#   #", "#   #"]),
+    ('N', ["#   #", "#   #", "##  #", "# # #", "#  ##", "#   #", "#   #"]),
+    ('O', [" ### ", "#   #", "#   #", "#   #", "#   #", "#   #", " ### "]),
+    ('P', ["#### ", "#   #", "#   #", "#### ", "#    ", "#    ", "#    "]),
+    ('Q', [" ### ", "#   #", "#   #", "#   #", "# # #", "#  # ", " ## #"]),
+    ('R', ["#### ", "#   #", "#   #", "#### ", "# #  ", "#  # ", "#   #"]),
+    ('S', [" ####", "#    ", "#    ", " ### ", "    #", "    #", "#### "]),
+    ('T', ["#####", "  #  ", "  #  ", "  #  ", "  #  ", "  #  ", "  #  "]),
+    ('U', ["#   #", "#   #", "#   #", "#   #", "#   #", "#   #", " ### "]),
+    ('V', ["#   #", "#   #", "#   #", "#   #", "#   #", " # # ", "  #  "]),
+    ('W', ["#   #", "#   #", "#   #", "# # #", "# # #", "# # #", " # # "]),
+    ('X', ["#   #", "#   #", " # # ", "  #  ", " # # ", "#   #", "#   #"]),
+    ('Y', ["#   #", "#   #", " # # ", "  #  ", "  #  ", "  #  ", "  #  "]),
+    ('Z', ["#####", "    #", "   # ", "  #  ", " #   ", "#    ", "#####"]),
+    ('[', [" ### ", " #   ", " #   ", " #   ", " #   ", " #   ", " ### "]),
+    (']', [" ### ", "   # ", "   # ", "   # ", "   # ", "   # ", " ### "]),
+    ('_', ["     ", "     ", "     ", "     ", "     ", "     ", "#####"]),
+    ('|', ["  #  ", "  #  ", "  #  ", "  #  ", "  #  ", "  #  ", "  #  "]),
+];
+
+fn glyph(character: char) -> &'static [&'static str; GLYPH_HEIGHT] {
+    let character = character.to_ascii_uppercase();
+    GLYPHS
+        .iter()
+        .find(|(c, _)| *c == character)
+        .or_else(|| GLYPHS.iter().find(|(c, _)| *c == '?'))
+        .map(|(_, rows)| rows)
+        .expect("the font has a '?'")
+}
+
+// width in pixels of text drawn at scale
+pub fn text_width(text: &str, scale: usize) -> usize {
+    let characters = text.chars().count();
+    (characters * (GLYPH_WIDTH + LETTER_SPACING)).saturating_sub(LETTER_SPACING) * scale
+}
+
+// draws text with its top left corner at x, y (y counts down from the top of the screen, like the buffer);
+// every font pixel becomes a scale * scale square, and whatever falls outside the screen is left out;
+// frame_width is the width of the buffer in pixels
+pub fn draw_text(
+    buffer: &mut [u32],
+    frame_width: usize,
+    x: usize,
+    y: usize,
+    text: &str,
+    color: u32,
+    scale: usize,
+) {
+    let frame_height = buffer.len() / frame_width;
+    for (i, character) in text.chars().enumerate() {
+        let left = x + i * (GLYPH_WIDTH + LETTER_SPACING) * scale;
+        for (row, pixels) in glyph(character).iter().enumerate() {
+            for (column, pixel) in pixels.bytes().enumerate() {
+                if pixel != b'#' {
+                    continue;
+                }
+                for screen_y in y + row * scale..y + (row + 1) * scale {
+                    for screen_x in left + column * scale..left + (column + 1) * scale {
+                        if screen_x < frame_width && screen_y < frame_height {
+                            buffer[screen_y * frame_width + screen_x] = color;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+// the same text one pixel down and right in black first, so it stays readable on any background
+pub fn draw_text_with_shadow(
+    buffer: &mut [u32],
+    frame_width: usize,
+    x: usize,
+    y: usize,
+    text: &str,
+    color: u32,
+    scale: usize,
+) {
+    draw_text(
+        buffer,
+        frame_width,
+        x + scale,
+        y + scale,
+        text,
+        SHADOW_COLOR,
+        scale,
+    );
+    draw_text(buffer, frame_width, x, y, text, color, scale);
+}
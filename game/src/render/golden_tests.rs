@@ -0,0 +1,180 @@
+// renders fixed poses on the maps in assets/maps and compares them with the reference images in tests/golden;
+// run `UPDATE_GOLDEN=1 cargo test golden` to rewrite the references after an intended change to the renderer
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::game::Game;
+use crate::render::image_output::encode_ppm;
+use crate::render::texture::Texture;
+use crate::render::{ViewMode, draw, render_init};
+use crate::{HORIZONTAL_FOV, SCREEN_HEIGHT, SCREEN_WIDTH};
+
+const GOLDEN_DIRECTORY: &str = "tests/golden";
+const DIFF_DIRECTORY: &str = "target/golden-diff";
+const CHANNEL_TOLERANCE: u32 = 8; // per color channel, absorbs rounding differences between platforms
+const MAX_DIFFERENT_PIXELS: f64 = 0.001; // share of pixels allowed to be off by more than the channel tolerance
+const DIFF_COLOR: u32 = 0xff0000;
+
+struct Pose {
+    x: f64,
+    y: f64,
+    angle_degrees: f64,
+    height: f64,
+    pitch_degrees: f64,
+}
+
+fn render(map: &str, pose: Pose, view_mode: ViewMode) -> Vec<u32> {
+    let mut game = Game::new(map).expect("test map should load");
+    game.player.position_x = pose.x;
+    game.player.position_y = pose.y;
+    game.player.view_angle = pose.angle_degrees.to_radians();
+    game.player.view_height = pose.height;
+    game.player.pitch = pose.pitch_degrees.to_radians();
+
+    let mut renderer_data = render_init(SCREEN_WIDTH, SCREEN_HEIGHT, HORIZONTAL_FOV);
+    renderer_data.view_mode = view_mode;
+    renderer_data.show_rays = true;
+
+    let mut buffer = vec![0; SCREEN_WIDTH * SCREEN_HEIGHT];
+    draw(&mut buffer, &renderer_data, &game);
+    buffer
+}
+
+fn assert_matches_golden(name: &str, actual: &[u32]) {
+    let golden_path = Path::new(GOLDEN_DIRECTORY).join(format!("{name}.ppm"));
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::create_dir_all(GOLDEN_DIRECTORY).unwrap();
+        fs::write(
+            &golden_path,
+            encode_ppm(actual, SCREEN_WIDTH, SCREEN_HEIGHT),
+        )
+        .unwrap();
+        return;
+    }
+
+    let golden = Texture::load(&golden_path).unwrap_or_else(|e| {
+        panic!("{e}\nrun `UPDATE_GOLDEN=1 cargo test golden` to create the reference image")
+    });
+    assert_eq!(
+        (golden.width, golden.height),
+        (SCREEN_WIDTH, SCREEN_HEIGHT),
+        "{} has a different resolution than the renderer",
+        golden_path.display()
+    );
+
+    let mut diff: Vec<u32> = Vec::with_capacity(actual.len());
+    let mut different_pixels = 0;
+    for (&actual_pixel, &golden_pixel) in actual.iter().zip(&golden.pixels) {
+        if channels_differ(actual_pixel, golden_pixel) {
+            different_pixels += 1;
+            diff.push(DIFF_COLOR);
+        } else {
+            diff.push((actual_pixel >> 2) & 0x3f3f3f); // darkened so the differences stand out
+        }
+    }
+
+    let different_share = different_pixels as f64 / actual.len() as f64;
+    if different_share > MAX_DIFFERENT_PIXELS {
+        let diff_directory = PathBuf::from(DIFF_DIRECTORY);
+        fs::create_dir_all(&diff_directory).unwrap();
+        let actual_path = diff_directory.join(format!("{name}_actual.ppm"));
+        let diff_path = diff_directory.join(format!("{name}_diff.ppm"));
+        fs::write(
+            &actual_path,
+            encode_ppm(actual, SCREEN_WIDTH, SCREEN_HEIGHT),
+        )
+        .unwrap();
+        fs::write(&diff_path, encode_ppm(&diff, SCREEN_WIDTH, SCREEN_HEIGHT)).unwrap();
+        panic!(
+            "{name}: {different_pixels} pixels ({:.2}%) differ from {}\nrendered image: {}\ndiff image: {}",
+            different_share * 100.0,
+            golden_path.display(),
+            actual_path.display(),
+            diff_path.display()
+        );
+    }
+}
+
+fn channels_differ(a: u32, b: u32) -> bool {
+    [16, 8, 0]
+        .iter()
+        .any(|shift| ((a >> shift) & 0xff).abs_diff((b >> shift) & 0xff) > CHANNEL_TOLERANCE)
+}
+
+#[test]
+fn golden_default_map_start() {
+    let pose = Pose {
+        x: 187.5,
+        y: 225.0,
+        angle_degrees: -90.0,
+        height: 10.0,
+        pitch_degrees: 0.0,
+    };
+    let frame = render("assets/maps/default.map", pose, ViewMode::Camera);
+    assert_matches_golden("default_map_start", &frame);
+}
+
+#[test]
+fn golden_default_map_platform() {
+    let pose = Pose {
+        x: 187.5,
+        y: 250.0,
+        angle_degrees: 131.8,
+        height: 10.0,
+        pitch_degrees: 0.0,
+    };
+    let frame = render("assets/maps/default.map", pose, ViewMode::Camera);
+    assert_matches_golden("default_map_platform", &frame);
+}
+
+#[test]
+fn golden_default_map_high_camera() {
+    let pose = Pose {
+        x: 150.0,
+        y: 260.0,
+        angle_degrees: -30.0,
+        height: 20.0,
+        pitch_degrees: 0.0,
+    };
+    let frame = render("assets/maps/default.map", pose, ViewMode::Camera);
+    assert_matches_golden("default_map_high_camera", &frame);
+}
+
+#[test]
+fn golden_default_map_looking_down() {
+    let pose = Pose {
+        x: 215.0,
+        y: 240.0,
+        angle_degrees: 50.0,
+        height: 20.0,
+        pitch_degrees: -30.0,
+    };
+    let frame = render("assets/maps/default.map", pose, ViewMode::Camera);
+    assert_matches_golden("default_map_looking_down", &frame);
+}
+
+#[test]
+fn golden_default_map_topdown() {
+    let pose = Pose {
+        x: 187.5,
+        y: 225.0,
+        angle_degrees: -60.0,
+        height: 10.0,
+        pitch_degrees: 0.0,
+    };
+    let frame = render("assets/maps/default.map", pose, ViewMode::TopDown);
+    assert_matches_golden("default_map_topdown", &frame);
+}
+
+#[test]
+fn golden_empty_example() {
+    let pose = Pose {
+        x: 40.0,
+        y: 160.0,
+        angle_degrees: -45.0,
+        height: 10.0,
+        pitch_degrees: 0.0,
+    };
+    let frame = render("assets/maps/emptyexample.map", pose, ViewMode::Camera);
+    assert_matches_golden("empty_example", &frame);
+}
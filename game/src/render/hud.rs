@@ -0,0 +1,171 @@
+use crate::game::Game;
+use crate::game::enemy::EnemyState;
+use crate::game::map::Point;
+use crate::render::camera_view::minimap_viewport;
+use crate::render::font::{LINE_HEIGHT, draw_text_with_shadow, text_width};
+use crate::render::renderer_init::{RendererData, ViewMode};
+
+const MARGIN: usize = 8; // pixels between the hud and the screen edges
+const TEXT_COLOR: u32 = 0xe0c060;
+const WARNING_COLOR: u32 = 0xe04030; // low health, no ammo left
+const DEBUG_COLOR: u32 = 0xffffff;
+const LOW_HEALTH: f64 = 25.0;
+const SMALL: usize = 1; // text scales
+const MEDIUM: usize = 2;
+const LARGE: usize = 3;
+const CROSSHAIR_COLOR: u32 = 0xffffff;
+const CROSSHAIR_SIZE: usize = 4; // pixels from the middle to the end of each line
+const REFERENCE_POINT_SPACING: usize = 50;
+const REFERENCE_POINT_COLOR: u32 = 0xff0000;
+
+// health and ammo at the bottom, map name, position and fps at the top, a crosshair in the middle
+pub fn draw_hud(buffer: &mut [u32], renderer_data: &RendererData, game: &Game, fps: f64) {
+    let player = &game.player;
+    let width = renderer_data.screen_width;
+
+    draw_text_with_shadow(
+        buffer,
+        width,
+        MARGIN,
+        MARGIN,
+        &game.map.metadata.name,
+        TEXT_COLOR,
+        MEDIUM,
+    );
+    let position = format!("X {:.0} Y {:.0}", player.position_x, player.position_y);
+    draw_text_with_shadow(
+        buffer,
+        width,
+        MARGIN,
+        MARGIN + LINE_HEIGHT * MEDIUM,
+        &position,
+        TEXT_COLOR,
+        SMALL,
+    );
+    // the minimap takes the top right corner, the fps go below it then
+    let fps_top = if renderer_data.view_mode == ViewMode::Camera && renderer_data.show_minimap {
+        let minimap = minimap_viewport(renderer_data);
+        minimap.y + minimap.height + MARGIN
+    } else {
+        MARGIN
+    };
+    let fps = format!("FPS {fps:.0}");
+    draw_right_aligned(buffer, width, fps_top, &fps, TEXT_COLOR, MEDIUM);
+
+    let bottom = renderer_data
+        .screen_height
+        .saturating_sub(MARGIN + LINE_HEIGHT * LARGE);
+    let health_color = if player.health <= LOW_HEALTH {
+        WARNING_COLOR
+    } else {
+        TEXT_COLOR
+    };
+    let health = format!("HEALTH {:.0}", player.health.max(0.0).ceil());
+    draw_text_with_shadow(buffer, width, MARGIN, bottom, &health, health_color, LARGE);
+    let weapon = player.weapon();
+    let ammo_color = if weapon.ammo == 0 {
+        WARNING_COLOR
+    } else {
+        TEXT_COLOR
+    };
+    let ammo = format!("{} {}", weapon.name, weapon.ammo);
+    draw_right_aligned(buffer, width, bottom, &ammo, ammo_color, LARGE);
+
+    if renderer_data.view_mode == ViewMode::Camera {
+        draw_crosshair(buffer, renderer_data);
+    }
+}
+
+fn draw_right_aligned(
+    buffer: &mut [u32],
+    frame_width: usize,
+    y: usize,
+    text: &str,
+    color: u32,
+    scale: usize,
+) {
+    let x = frame_width.saturating_sub(MARGIN + text_width(text, scale));
+    draw_text_with_shadow(buffer, frame_width, x, y, text, color, scale);
+}
+
+// shots go where the middle of the screen looks, see Game::fire
+fn draw_crosshair(buffer: &mut [u32], renderer_data: &RendererData) {
+    let width = renderer_data.screen_width;
+    let (center_x, center_y) = (width / 2, renderer_data.screen_height / 2);
+    for offset in 2..=CROSSHAIR_SIZE {
+        // arms that would leave a tiny frame are cut off
+        for (x, y) in [
+            (center_x.checked_sub(offset), Some(center_y)),
+            (Some(center_x + offset), Some(center_y)),
+            (Some(center_x), center_y.checked_sub(offset)),
+            (Some(center_x), Some(center_y + offset)),
+        ] {
+            if let (Some(x), Some(y)) = (x, y)
+                && x < width
+                && y < renderer_data.screen_height
+            {
+                buffer[y * width + x] = CROSSHAIR_COLOR;
+            }
+        }
+    }
+}
+
+// grid of reference points spaced each 50 pixels and the state of the player and renderer, toggled with F3
+pub fn draw_debug_overlay(buffer: &mut [u32], renderer_data: &RendererData, game: &Game) {
+    let width = renderer_data.screen_width;
+    for y in (0..renderer_data.screen_height).step_by(REFERENCE_POINT_SPACING) {
+        for x in (0..width).step_by(REFERENCE_POINT_SPACING) {
+            buffer[y * width + x] = REFERENCE_POINT_COLOR;
+        }
+    }
+
+    let player = &game.player;
+    let view = renderer_data.scaled_view();
+    let light = game
+        .map
+        .surfaces_at(Point {
+            x: player.position_x,
+            y: player.position_y,
+        })
+        .light;
+    let alive = game
+        .enemies
+        .iter()
+        .filter(|enemy| enemy.state != EnemyState::Dead)
+        .count();
+    let lines = [
+        format!(
+            "POSITION {:.1} {:.1} HEIGHT {:.1}",
+            player.position_x, player.position_y, player.view_height
+        ),
+        format!(
+            "ANGLE {:.1} PITCH {:.1}",
+            player.view_angle.to_degrees(),
+            player.pitch.to_degrees()
+        ),
+        format!(
+            "VERTICAL VELOCITY {:.1} ON GROUND {} NOCLIP {}",
+            player.vertical_velocity, player.on_ground, player.noclip
+        ),
+        format!("ENEMIES {alive}/{}", game.enemies.len()),
+        format!("LIGHT {:.2}", game.map.light_level(light, game.time)),
+        format!("RENDER THREADS {}", renderer_data.threads),
+        format!(
+            "RENDER SCALE {} ({}x{})",
+            renderer_data.render_scale, view.screen_width, view.screen_height
+        ),
+    ];
+    // below the map name and position of the hud
+    let top = MARGIN + LINE_HEIGHT * (MEDIUM + 2 * SMALL);
+    for (i, line) in lines.iter().enumerate() {
+        draw_text_with_shadow(
+            buffer,
+            width,
+            MARGIN,
+            top + i * LINE_HEIGHT * SMALL,
+            line,
+            DEBUG_COLOR,
+            SMALL,
+        );
+    }
+}
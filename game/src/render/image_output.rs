@@ -0,0 +1,191 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// writes a frame buffer (0x00RRGGBB, row-major, row 0 at the top) as an image; the format is picked by the file extension
+pub fn save_image<P: AsRef<Path>>(
+    path: P,
+    buffer: &[u32],
+    width: usize,
+    height: usize,
+) -> io::Result<()> {
+    let path = path.as_ref();
+    let bytes = match path.extension().and_then(|e| e.to_str()) {
+        Some(extension) if extension.eq_ignore_ascii_case("png") => {
+            encode_png(buffer, width, height)
+        }
+        Some(extension) if extension.eq_ignore_ascii_case("ppm") => {
+            encode_ppm(buffer, width, height)
+        }
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{}: only .ppm and .png are supported", path.display()),
+            ));
+        }
+    };
+    fs::write(path, bytes)
+}
+
+pub fn encode_ppm(buffer: &[u32], width: usize, height: usize) -> Vec<u8> {
+    let mut bytes = format!("P6\n{width} {height}\n255\n").into_bytes();
+    for &pixel in &buffer[..width * height] {
+        bytes.extend_from_slice(&rgb(pixel));
+    }
+    bytes
+}
+
+// uncompressed png: the image data goes into "stored" deflate blocks, so no compression library is needed
+pub fn encode_png(buffer: &[u32], width: usize, height: usize) -> Vec<u8> {
+    let mut raw = Vec::with_capacity((width * 3 + 1) * height);
+    for row in buffer[..width * height].chunks_exact(width) {
+        raw.push(0); // filter type none
+        for &pixel in row {
+            raw.extend_from_slice(&rgb(pixel));
+        }
+    }
+
+    let mut zlib = vec![0x78, 0x01]; // deflate, 32K window, no preset dictionary
+    const MAX_STORED_BLOCK: usize = 65_535;
+    let mut blocks = raw.chunks(MAX_STORED_BLOCK).peekable();
+    if blocks.peek().is_none() {
+        zlib.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        let last = blocks.peek().is_none();
+        let length = block.len() as u16;
+        zlib.push(last as u8);
+        zlib.extend_from_slice(&length.to_le_bytes());
+        zlib.extend_from_slice(&(!length).to_le_bytes());
+        zlib.extend_from_slice(block);
+    }
+    zlib.extend_from_slice(&adler32(&raw).to_be_bytes());
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&(width as u32).to_be_bytes());
+    header.extend_from_slice(&(height as u32).to_be_bytes());
+    header.extend_from_slice(&[8, 2, 0, 0, 0]); // 8 bit, truecolor, deflate, default filtering, no interlace
+
+    let mut png = vec![0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+    write_chunk(&mut png, b"IHDR", &header);
+    write_chunk(&mut png, b"IDAT", &zlib);
+    write_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+fn rgb(pixel: u32) -> [u8; 3] {
+    [(pixel >> 16) as u8, (pixel >> 8) as u8, pixel as u8]
+}
+
+fn write_chunk(png: &mut Vec<u8>, chunk_type: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(chunk_type);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = 0xffff_ffffu32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xedb8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in bytes {
+        a = (a + byte as u32) % 65_521;
+        b = (b + a) % 65_521;
+    }
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // splits a png into its chunks, checking the signature and the crc of each
+    fn chunks(png: &[u8]) -> Vec<([u8; 4], &[u8])> {
+        assert_eq!(
+            png[..8],
+            [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n']
+        );
+        let mut chunks = Vec::new();
+        let mut rest = &png[8..];
+        while !rest.is_empty() {
+            let length = u32::from_be_bytes(rest[..4].try_into().unwrap()) as usize;
+            let (typed, crc) = rest[4..].split_at(4 + length);
+            assert_eq!(
+                crc32(typed),
+                u32::from_be_bytes(crc[..4].try_into().unwrap())
+            );
+            chunks.push((typed[..4].try_into().unwrap(), &typed[4..]));
+            rest = &crc[4..];
+        }
+        chunks
+    }
+
+    // undoes the stored deflate blocks of the zlib stream
+    fn inflate_stored(zlib: &[u8]) -> Vec<u8> {
+        assert_eq!(zlib[..2], [0x78, 0x01]);
+        let mut raw = Vec::new();
+        let mut rest = &zlib[2..];
+        loop {
+            let last = rest[0] == 1;
+            let length = u16::from_le_bytes([rest[1], rest[2]]);
+            assert_eq!(!length, u16::from_le_bytes([rest[3], rest[4]]));
+            raw.extend_from_slice(&rest[5..5 + length as usize]);
+            rest = &rest[5 + length as usize..];
+            if last {
+                break;
+            }
+        }
+        assert_eq!(rest, adler32(&raw).to_be_bytes());
+        raw
+    }
+
+    #[test]
+    fn checksums_match_the_reference_values() {
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+    }
+
+    #[test]
+    fn ppm_has_header_and_rgb_bytes() {
+        let buffer = [0xff0000, 0x00ff00, 0x0000ff, 0x123456];
+        let mut expected = b"P6\n2 2\n255\n".to_vec();
+        expected.extend_from_slice(&[255, 0, 0, 0, 255, 0, 0, 0, 255, 0x12, 0x34, 0x56]);
+        assert_eq!(encode_ppm(&buffer, 2, 2), expected);
+    }
+
+    #[test]
+    fn png_holds_the_image_across_stored_blocks() {
+        // one row is 601 bytes, so the image data needs two stored blocks
+        let (width, height) = (200, 150);
+        let buffer: Vec<u32> = (0..width * height).map(|i| i as u32 * 0x010203).collect();
+        let png = encode_png(&buffer, width, height);
+        let chunks = chunks(&png);
+        let types: Vec<&[u8; 4]> = chunks.iter().map(|(chunk_type, _)| chunk_type).collect();
+        assert_eq!(types, [b"IHDR", b"IDAT", b"IEND"]);
+
+        let header = chunks[0].1;
+        assert_eq!(header[..4], (width as u32).to_be_bytes());
+        assert_eq!(header[4..8], (height as u32).to_be_bytes());
+
+        let raw = inflate_stored(chunks[1].1);
+        assert!(raw.len() > 65_535);
+        for (y, row) in raw.chunks_exact(width * 3 + 1).enumerate() {
+            assert_eq!(row[0], 0);
+            for (x, pixel) in row[1..].chunks_exact(3).enumerate() {
+                assert_eq!(pixel, rgb(buffer[y * width + x]));
+            }
+        }
+    }
+}
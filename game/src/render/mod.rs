@@ -1,7 +1,17 @@
 pub mod camera_view;
+#[cfg(test)]
+mod column_bench;
+pub mod font;
+#[cfg(test)]
+mod golden_tests;
+pub mod hud;
+pub mod image_output;
 pub mod raycast;
 mod renderer_init;
+pub mod screens;
+mod sprites;
+pub mod texture;
 pub mod topdown_view;
 
 pub use camera_view::draw;
-pub use renderer_init::{RendererData, render_init};
+pub use renderer_init::{RendererData, ViewMode, available_threads, render_init};
@@ -1,25 +1,28 @@
+#[derive(Clone, Copy, PartialEq)]
+pub enum ViewMode {
+    Camera,
+    TopDown,
+}
+
 pub struct RendererData {
+    pub screen_width: usize, // size of the frame buffer everything is drawn into
+    pub screen_height: usize,
     pub screen_width_as_f64: f64,
     pub screen_height_as_f64: f64,
     pub horizontal_fov: f64,
     pub vertical_fov: f64,
     pub vertical_scale_coefficient: f64,
     pub projection_plane_distance: f64,
-    pub background_color: u32,
-    pub wall_default_color: u32,
-    pub block_default_color: u32,
-    pub distance_darkness_coefficient: f64,
+    // toggled at runtime, see main.rs
+    pub view_mode: ViewMode,
+    pub show_minimap: bool,
+    pub show_rays: bool,   // draw the cast rays in the top down view
+    pub show_debug: bool,  // reference grid and player state over the view
+    pub threads: usize, // threads rendering the camera view; 1 renders everything on the calling thread
+    pub render_scale: f64, // size of the camera view relative to the frame, it is scaled up to fill the frame
 }
 
-pub fn render_init(
-    screen_width: usize,
-    screen_height: usize,
-    horizontal_fov: f64,
-    background_color: u32,
-    distance_darkness_coefficient: f64,
-    wall_default_color: u32,
-    block_default_color: u32,
-) -> RendererData {
+pub fn render_init(screen_width: usize, screen_height: usize, horizontal_fov: f64) -> RendererData {
     let screen_width_as_f64 = screen_width as f64;
     let screen_height_as_f64 = screen_height as f64;
 
@@ -31,15 +34,50 @@ pub fn render_init(
     let projection_plane_distance: f64 = (screen_width as f64 / 2.0) / (horizontal_fov / 2.0).tan();
 
     RendererData {
+        screen_width,
+        screen_height,
         screen_width_as_f64,
         screen_height_as_f64,
         horizontal_fov,
         vertical_fov,
         vertical_scale_coefficient,
         projection_plane_distance,
-        background_color,
-        distance_darkness_coefficient,
-        wall_default_color,
-        block_default_color,
+        view_mode: ViewMode::Camera,
+        show_minimap: false,
+        show_rays: false,
+        show_debug: false,
+        threads: available_threads(),
+        render_scale: 1.0,
+    }
+}
+
+impl RendererData {
+    // the same view at the size the camera view is rendered at; hud and top down view stay at the frame size
+    pub fn scaled_view(&self) -> RendererData {
+        let scale =
+            |size: usize| ((size as f64 * self.render_scale).round() as usize).clamp(1, size);
+        RendererData {
+            view_mode: self.view_mode,
+            show_minimap: self.show_minimap,
+            show_rays: self.show_rays,
+            show_debug: self.show_debug,
+            threads: self.threads,
+            ..render_init(
+                scale(self.screen_width),
+                scale(self.screen_height),
+                self.horizontal_fov,
+            )
+        }
     }
+
+    // row (counted from the bottom of the screen) where the horizon ends up when looking up or down by pitch radians;
+    // looking up shears the whole view down instead of rotating the camera, so walls stay vertical
+    pub fn horizon(&self, pitch: f64) -> f64 {
+        self.screen_height_as_f64 / 2.0 - pitch.tan() * self.vertical_scale_coefficient
+    }
+}
+
+// one render thread per core, or a single one if that can't be found out
+pub fn available_threads() -> usize {
+    std::thread::available_parallelism().map_or(1, |threads| threads.get())
 }
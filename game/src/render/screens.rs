@@ -0,0 +1,226 @@
+use crate::game::Game;
+use crate::render::RendererData;
+use crate::render::font::{
+    GLYPH_HEIGHT, GLYPH_WIDTH, LETTER_SPACING, LINE_HEIGHT, draw_text, draw_text_with_shadow,
+    text_width,
+};
+use crate::render::topdown_view::{Viewport, draw_topdown};
+
+const MENU_BACKGROUND: u32 = 0x101018;
+const MENU_LIST_WIDTH: usize = 200; // left part of the screen, the preview of the selected map takes the rest
+const MENU_ENTRY_HEIGHT: usize = 20;
+const MENU_ENTRY_SPACING: usize = 30;
+const MENU_MARGIN: usize = 20;
+const ENTRY_COLOR: u32 = 0x505060;
+const SELECTED_ENTRY_COLOR: u32 = 0xc0c0d0;
+const BROKEN_ENTRY_COLOR: u32 = 0x803030; // the map could not be loaded
+const ENTRY_TEXT_COLOR: u32 = 0xffffff;
+const SELECTED_ENTRY_TEXT_COLOR: u32 = 0x101018;
+const ENTRY_TEXT_PADDING: usize = 6;
+const HINT_COLOR: u32 = 0x808090;
+const MESSAGE_COLOR: u32 = 0xe0c060;
+const BANNER_COLOR: u32 = 0xffffff;
+const BANNER_SCALE: usize = 5;
+const HINT_SCALE: usize = 2;
+
+// one bar per map with its name on the left and the top down view of the selected one on the right;
+// an entry without a preview is a map that failed to load
+pub fn draw_menu(
+    buffer: &mut [u32],
+    renderer_data: &RendererData,
+    entries: &[(&str, Option<&Game>)],
+    selected: usize,
+) {
+    let (width, height) = (renderer_data.screen_width, renderer_data.screen_height);
+    buffer.fill(MENU_BACKGROUND);
+
+    let hints = ["UP/DOWN: SELECT", "ENTER: PLAY", "O: OPTIONS", "ESC: QUIT"];
+    let hints_top = height.saturating_sub(MENU_MARGIN + hints.len() * LINE_HEIGHT);
+    // the list ends above the hints, but always has room for the selected entry, even on tiny frames
+    let list_bottom = hints_top.max(MENU_MARGIN + MENU_ENTRY_HEIGHT).min(height);
+    let visible =
+        (list_bottom.saturating_sub(MENU_MARGIN + MENU_ENTRY_HEIGHT) / MENU_ENTRY_SPACING + 1)
+            .min(entries.len());
+    // the list scrolls just far enough to show the selected entry at the bottom
+    let first = (selected + 1).saturating_sub(visible);
+
+    for (i, &(name, entry)) in entries.iter().enumerate().skip(first).take(visible) {
+        let color = match entry {
+            None => BROKEN_ENTRY_COLOR,
+            Some(_) if i == selected => SELECTED_ENTRY_COLOR,
+            Some(_) => ENTRY_COLOR,
+        };
+        let top = MENU_MARGIN + (i - first) * MENU_ENTRY_SPACING;
+        // on narrow frames the bars are cut off at the right edge
+        let (left, right) = (
+            MENU_MARGIN.min(width),
+            (MENU_LIST_WIDTH - MENU_MARGIN).min(width),
+        );
+        for y in top..(top + MENU_ENTRY_HEIGHT).min(list_bottom) {
+            let row = y * width;
+            buffer[row + left..row + right].fill(color);
+        }
+        // the broken entry that is selected gets a light frame, so the selection is always visible
+        if entry.is_none() && i == selected && top + MENU_ENTRY_HEIGHT <= list_bottom {
+            for x in left..right {
+                buffer[top * width + x] = SELECTED_ENTRY_COLOR;
+                buffer[(top + MENU_ENTRY_HEIGHT - 1) * width + x] = SELECTED_ENTRY_COLOR;
+            }
+        }
+        let text_color = if entry.is_some() && i == selected {
+            SELECTED_ENTRY_TEXT_COLOR
+        } else {
+            ENTRY_TEXT_COLOR
+        };
+        draw_text(
+            buffer,
+            width,
+            MENU_MARGIN + ENTRY_TEXT_PADDING,
+            top + (MENU_ENTRY_HEIGHT - GLYPH_HEIGHT) / 2,
+            name,
+            text_color,
+            1,
+        );
+    }
+    for (i, hint) in hints.iter().enumerate() {
+        draw_text(
+            buffer,
+            width,
+            MENU_MARGIN,
+            hints_top + i * LINE_HEIGHT,
+            hint,
+            HINT_COLOR,
+            1,
+        );
+    }
+
+    if let Some((_, Some(preview))) = entries.get(selected) {
+        let viewport = Viewport {
+            x: MENU_LIST_WIDTH,
+            y: MENU_MARGIN,
+            width: width.saturating_sub(MENU_LIST_WIDTH + MENU_MARGIN),
+            height: height.saturating_sub(2 * MENU_MARGIN),
+            stride: width,
+        };
+        draw_topdown(buffer, renderer_data, preview, viewport);
+    }
+}
+
+// large centered title with a line of smaller text below it, for the screens drawn over a frozen game
+pub fn draw_banner(buffer: &mut [u32], renderer_data: &RendererData, title: &str, hint: &str) {
+    let width = renderer_data.screen_width;
+    let title_height = LINE_HEIGHT * BANNER_SCALE;
+    let top = renderer_data
+        .screen_height
+        .saturating_sub(title_height + LINE_HEIGHT * HINT_SCALE)
+        / 2;
+    for (text, y, scale) in [
+        (title, top, BANNER_SCALE),
+        (hint, top + title_height, HINT_SCALE),
+    ] {
+        let x = width.saturating_sub(text_width(text, scale)) / 2;
+        draw_text_with_shadow(buffer, width, x, y, text, BANNER_COLOR, scale);
+    }
+}
+
+// full screen page of text: a title, one line per entry, an optional highlighted message and a hint at the bottom
+pub fn draw_text_screen(
+    buffer: &mut [u32],
+    renderer_data: &RendererData,
+    title: &str,
+    lines: &[String],
+    message: Option<&str>,
+    hint: &str,
+) {
+    let width = renderer_data.screen_width;
+    buffer.fill(MENU_BACKGROUND);
+    draw_text(
+        buffer,
+        width,
+        MENU_MARGIN,
+        MENU_MARGIN,
+        title,
+        SELECTED_ENTRY_COLOR,
+        HINT_SCALE,
+    );
+    let top = MENU_MARGIN + LINE_HEIGHT * (HINT_SCALE + 1);
+    for (i, line) in lines.iter().enumerate() {
+        draw_text(
+            buffer,
+            width,
+            MENU_MARGIN,
+            top + i * LINE_HEIGHT,
+            line,
+            ENTRY_TEXT_COLOR,
+            1,
+        );
+    }
+    let bottom = renderer_data
+        .screen_height
+        .saturating_sub(MENU_MARGIN + LINE_HEIGHT);
+    if let Some(message) = message {
+        // long messages (parse errors list what was expected) are wrapped at the screen edge
+        let characters_per_line =
+            (width.saturating_sub(2 * MENU_MARGIN) / (GLYPH_WIDTH + LETTER_SPACING)).max(1);
+        let characters: Vec<char> = message.chars().collect();
+        let message_lines: Vec<String> = characters
+            .chunks(characters_per_line)
+            .map(|chunk| chunk.iter().collect())
+            .collect();
+        let message_top = bottom.saturating_sub((message_lines.len() + 1) * LINE_HEIGHT);
+        for (i, line) in message_lines.iter().enumerate() {
+            draw_text(
+                buffer,
+                width,
+                MENU_MARGIN,
+                message_top + i * LINE_HEIGHT,
+                line,
+                MESSAGE_COLOR,
+                1,
+            );
+        }
+    }
+    draw_text(buffer, width, MENU_MARGIN, bottom, hint, HINT_COLOR, 1);
+}
+
+// blends the whole frame towards color; amount 0 leaves it alone, 1 covers it completely
+pub fn tint(buffer: &mut [u32], color: u32, amount: f64) {
+    let blend = |pixel: u32, shift: u32| {
+        let from = ((pixel >> shift) & 0xff) as f64;
+        let to = ((color >> shift) & 0xff) as f64;
+        ((from + (to - from) * amount).round() as u32) << shift
+    };
+    for pixel in buffer.iter_mut() {
+        *pixel = blend(*pixel, 16) | blend(*pixel, 8) | blend(*pixel, 0);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::render::renderer_init::render_init;
+
+    // rows of the list column that have the color
+    fn rows_with(buffer: &[u32], width: usize, color: u32) -> Vec<usize> {
+        (0..buffer.len() / width)
+            .filter(|y| buffer[y * width + MENU_MARGIN + 1] == color)
+            .collect()
+    }
+
+    #[test]
+    fn menu_scrolls_to_the_selected_entry_above_the_hints() {
+        let (width, height) = (320, 240);
+        let renderer_data = render_init(width, height, 90f64.to_radians());
+        let entries = [("BROKEN", None); 20];
+        let hints_top = height - MENU_MARGIN - 4 * LINE_HEIGHT;
+        for selected in [0, 10, 19] {
+            let mut buffer = vec![0; width * height];
+            draw_menu(&mut buffer, &renderer_data, &entries, selected);
+            let bars = rows_with(&buffer, width, BROKEN_ENTRY_COLOR);
+            assert!(bars.iter().all(|&y| y >= MENU_MARGIN && y < hints_top));
+            let frame = rows_with(&buffer, width, SELECTED_ENTRY_COLOR);
+            assert_eq!(frame.len(), 2, "selected {selected}");
+            assert!(frame[1] < hints_top);
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use crate::game::Game;
+use crate::game::map::{Point, Sprite};
+use crate::render::camera_view::{Column, brightness, shade};
+use crate::render::renderer_init::RendererData;
+use crate::render::texture::TRANSPARENT;
+
+const NEAR_PLANE: f64 = 1.0; // sprites closer than this (or behind the camera) are not drawn
+
+// a sprite as seen from the camera, in screen coordinates
+struct ProjectedSprite<'a> {
+    sprite: &'a Sprite,
+    depth: f64, // distance along the view direction
+    left: f64,  // screen x of the left edge
+    width: f64, // in pixels
+    bottom_row: f64,
+    top_row: f64, // rows count from the bottom of the screen, like in the columns
+    brightness: f64,
+}
+
+// draws the map's sprites and the enemies as billboards that always face the camera; farther sprites are drawn first
+// and every pixel is only drawn where nothing nearer was drawn into the column before
+pub fn draw_sprites(columns: &mut [Column], renderer_data: &RendererData, game: &Game) {
+    let enemy_sprites: Vec<Sprite> = game
+        .enemies
+        .iter()
+        .filter_map(|enemy| enemy.sprite(&game.map))
+        .collect();
+    let mut projected: Vec<ProjectedSprite> = game
+        .map
+        .sprites
+        .iter()
+        .chain(&enemy_sprites)
+        .filter_map(|sprite| project(sprite, renderer_data, game))
+        .collect();
+    projected.sort_by(|a, b| b.depth.total_cmp(&a.depth));
+
+    for sprite in &projected {
+        draw_sprite(columns, sprite, game);
+    }
+}
+
+fn project<'a>(
+    sprite: &'a Sprite,
+    renderer_data: &RendererData,
+    game: &Game,
+) -> Option<ProjectedSprite<'a>> {
+    let player = &game.player;
+    let player_position = Point {
+        x: player.position_x,
+        y: player.position_y,
+    };
+    let relative = sprite.position - player_position;
+    let forward = Point {
+        x: player.view_angle.cos(),
+        y: player.view_angle.sin(),
+    };
+    // screen x grows with the angle, so right on screen is a quarter turn counterclockwise from forward
+    let right = Point {
+        x: -forward.y,
+        y: forward.x,
+    };
+    let depth = relative.dot(forward);
+    if depth < NEAR_PLANE {
+        return None;
+    }
+
+    let center_x = renderer_data.screen_width_as_f64 / 2.0
+        + relative.dot(right) / depth * renderer_data.projection_plane_distance;
+    let width = sprite.width / depth * renderer_data.projection_plane_distance;
+
+    // sprites stand on whatever floor is below them and are lit like the region they are in
+    let surfaces = game.map.surfaces_at(sprite.position);
+    let bottom = surfaces.floor_height + sprite.elevation;
+    let horizon = renderer_data.horizon(player.pitch);
+    let row_of = |z: f64| {
+        horizon + (z - player.view_height) / depth * renderer_data.vertical_scale_coefficient
+    };
+
+    Some(ProjectedSprite {
+        sprite,
+        depth,
+        left: center_x - width / 2.0,
+        width,
+        bottom_row: row_of(bottom),
+        top_row: row_of(bottom + sprite.height),
+        brightness: brightness(
+            relative.length(),
+            1.0,
+            game.map.metadata.distance_darkness_coefficient,
+        ) * game.map.light_level(surfaces.light, game.time),
+    })
+}
+
+fn draw_sprite(columns: &mut [Column], projected: &ProjectedSprite, game: &Game) {
+    let Some(texture) = game.map.textures.get(projected.sprite.texture) else {
+        return;
+    };
+
+    let first_x = projected.left.round().max(0.0) as usize;
+    let end_x = ((projected.left + projected.width).round().max(0.0) as usize).min(columns.len());
+    let first_row = projected.bottom_row.round().max(0.0) as usize;
+    let height_in_rows = projected.top_row - projected.bottom_row;
+
+    for (x, column) in columns.iter_mut().enumerate().take(end_x).skip(first_x) {
+        let u = (x as f64 + 0.5 - projected.left) / projected.width;
+        let end_row = (projected.top_row.round().max(0.0) as usize).min(column.pixels.len());
+        for row in first_row..end_row {
+            if column.depth[row] <= projected.depth {
+                continue;
+            }
+            let v = (row as f64 + 0.5 - projected.bottom_row) / height_in_rows;
+            let color = texture.sample(u, v);
+            if color == TRANSPARENT {
+                continue;
+            }
+            column.paint(row, shade(color, projected.brightness), projected.depth);
+        }
+    }
+}
@@ -0,0 +1,202 @@
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// textures are stored as PPM images (binary P6 or ascii P3), which every image editor can export and which need no extra dependency
+pub struct Texture {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<u32>, // row-major, row 0 is the top of the image, 0x00RRGGBB like the frame buffer
+}
+
+#[derive(Debug)]
+pub enum TextureError {
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    Parse {
+        path: PathBuf,
+        reason: String,
+    },
+}
+
+impl fmt::Display for TextureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TextureError::Io { path, source } => {
+                write!(f, "could not read texture {}: {source}", path.display())
+            }
+            TextureError::Parse { path, reason } => {
+                write!(f, "could not parse texture {}: {reason}", path.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for TextureError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TextureError::Io { source, .. } => Some(source),
+            TextureError::Parse { .. } => None,
+        }
+    }
+}
+
+impl Texture {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, TextureError> {
+        let path = path.as_ref();
+        let bytes = fs::read(path).map_err(|source| TextureError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        Self::from_ppm(&bytes).map_err(|reason| TextureError::Parse {
+            path: path.to_path_buf(),
+            reason,
+        })
+    }
+
+    pub fn from_ppm(bytes: &[u8]) -> Result<Self, String> {
+        let mut reader = PpmReader { bytes, position: 0 };
+
+        let magic = reader.token()?;
+        let binary = match magic.as_str() {
+            "P6" => true,
+            "P3" => false,
+            _ => return Err(format!("expected a P6 or P3 ppm image, found {magic:?}")),
+        };
+        let width = reader.number()?;
+        let height = reader.number()?;
+        let max_value = reader.number()?;
+        if width == 0 || height == 0 {
+            return Err(format!("image is empty ({width}x{height})"));
+        }
+        if max_value == 0 || max_value > 255 {
+            return Err(format!(
+                "only 8 bit images are supported, max value is {max_value}"
+            ));
+        }
+
+        // the header is not trusted: the sizes could overflow, and nothing is allocated before the data is there
+        let values = width
+            .checked_mul(height)
+            .and_then(|pixels| pixels.checked_mul(3))
+            .ok_or_else(|| format!("image is too large ({width}x{height})"))?;
+        let too_short = || String::from("image data is shorter than width * height");
+        let mut pixels;
+        if binary {
+            reader.position += 1; // exactly one whitespace byte separates the header from the data
+            let data = reader
+                .position
+                .checked_add(values)
+                .and_then(|end| bytes.get(reader.position..end))
+                .ok_or_else(too_short)?;
+            pixels = Vec::with_capacity(values / 3);
+            for rgb in data.chunks_exact(3) {
+                pixels.push(pack_rgb(
+                    rgb[0] as usize,
+                    rgb[1] as usize,
+                    rgb[2] as usize,
+                    max_value,
+                ));
+            }
+        } else {
+            // every value takes at least a digit and, except for the last one, a separator
+            if bytes.len().saturating_sub(reader.position) < values.saturating_mul(2) - 1 {
+                return Err(too_short());
+            }
+            pixels = Vec::with_capacity(values / 3);
+            for _ in 0..values / 3 {
+                let r = reader.number()?;
+                let g = reader.number()?;
+                let b = reader.number()?;
+                pixels.push(pack_rgb(r, g, b, max_value));
+            }
+        }
+
+        Ok(Texture {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    // u and v are wrapped into [0, 1), so values outside of it tile the texture; v = 0 is the bottom of the image
+    pub fn sample(&self, u: f64, v: f64) -> u32 {
+        let x = ((u.rem_euclid(1.0) * self.width as f64) as usize).min(self.width - 1);
+        let y_from_bottom =
+            ((v.rem_euclid(1.0) * self.height as f64) as usize).min(self.height - 1);
+        self.pixels[(self.height - 1 - y_from_bottom) * self.width + x]
+    }
+}
+
+// sprite images leave out their magenta pixels
+pub const TRANSPARENT: u32 = 0xff00ff;
+
+fn pack_rgb(r: usize, g: usize, b: usize, max_value: usize) -> u32 {
+    let scale = |channel: usize| (channel.min(max_value) * 255 / max_value) as u32;
+    (scale(r) << 16) | (scale(g) << 8) | scale(b)
+}
+
+// reads the whitespace separated header (and ascii data) of a ppm file, skipping # comments
+struct PpmReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl PpmReader<'_> {
+    fn token(&mut self) -> Result<String, String> {
+        loop {
+            match self.bytes.get(self.position) {
+                Some(b'#') => {
+                    while let Some(&byte) = self.bytes.get(self.position) {
+                        self.position += 1;
+                        if byte == b'\n' {
+                            break;
+                        }
+                    }
+                }
+                Some(byte) if byte.is_ascii_whitespace() => self.position += 1,
+                Some(_) => break,
+                None => return Err("unexpected end of file".to_string()),
+            }
+        }
+        let start = self.position;
+        while let Some(byte) = self.bytes.get(self.position) {
+            if byte.is_ascii_whitespace() {
+                break;
+            }
+            self.position += 1;
+        }
+        Ok(String::from_utf8_lossy(&self.bytes[start..self.position]).into_owned())
+    }
+
+    fn number(&mut self) -> Result<usize, String> {
+        let token = self.token()?;
+        token
+            .parse()
+            .map_err(|_| format!("expected a number, found {token:?}"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parses_binary_and_ascii() {
+        let binary = Texture::from_ppm(b"P6 2 1 255\n\xff\x00\x00\x00\x00\xff").unwrap();
+        assert_eq!(binary.pixels, vec![0xff0000, 0x0000ff]);
+        let ascii = Texture::from_ppm(b"P3 2 1 255\n255 0 0 0 0 255").unwrap();
+        assert_eq!(ascii.pixels, binary.pixels);
+    }
+
+    #[test]
+    fn rejects_huge_headers() {
+        let huge = format!("P6 {} {} 255\n", usize::MAX, 2);
+        assert!(Texture::from_ppm(huge.as_bytes()).is_err());
+        // fits into usize but not into the data that is there
+        assert!(Texture::from_ppm(b"P6 100000 100000 255\n\x00\x00\x00").is_err());
+        assert!(Texture::from_ppm(b"P3 100000 100000 255\n0 0 0").is_err());
+    }
+}
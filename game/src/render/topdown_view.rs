@@ -1 +1,310 @@
-// TODO implement; lots of the code is currently in camera view, commented out
+use crate::game::Game;
+use crate::game::enemy::EnemyState;
+use crate::game::map::{Point, Shape, ShapeType};
+use crate::render::renderer_init::RendererData;
+
+const MAP_MARGIN: f64 = 0.05; // part of the viewport left empty on each side of the map
+const FRAME_COLOR: u32 = 0xffffff;
+const BORDER_COLOR: u32 = 0x888888;
+const PLAYER_COLOR: u32 = 0xff0000;
+const PLAYER_DIRECTION_COLOR: u32 = 0x00ffff;
+const PLAYER_DIRECTION_LENGTH: f64 = 15.0; // in world units
+const RAY_COLOR: u32 = 0xffff00;
+const ENEMY_COLOR: u32 = 0xff8800;
+const DEAD_ENEMY_COLOR: u32 = 0x804400;
+const EXIT_COLOR: u32 = 0x40c0ff;
+const RAY_SPACING: usize = 10; // only every n-th screen column gets its ray drawn, otherwise the map is just yellow
+
+// rectangle of the screen the top down view is drawn into; x and y are the top left corner
+#[derive(Clone, Copy)]
+pub struct Viewport {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+    pub stride: usize, // width of the whole frame buffer the viewport lies in
+}
+
+impl Viewport {
+    pub fn full_screen(renderer_data: &RendererData) -> Self {
+        Viewport {
+            x: 0,
+            y: 0,
+            width: renderer_data.screen_width,
+            height: renderer_data.screen_height,
+            stride: renderer_data.screen_width,
+        }
+    }
+
+    fn contains(&self, x: isize, y: isize) -> bool {
+        x >= self.x as isize
+            && y >= self.y as isize
+            && x < (self.x + self.width) as isize
+            && y < (self.y + self.height) as isize
+    }
+}
+
+// scales and moves the map so that its border fits into the viewport
+struct MapTransform {
+    scale: f64,
+    offset_x: f64,
+    offset_y: f64,
+}
+
+impl MapTransform {
+    fn fit(border: &Shape, viewport: &Viewport) -> Self {
+        let mut min = Point {
+            x: f64::INFINITY,
+            y: f64::INFINITY,
+        };
+        let mut max = Point {
+            x: f64::NEG_INFINITY,
+            y: f64::NEG_INFINITY,
+        };
+        for side in &border.sides {
+            min.x = min.x.min(side.point1.x);
+            min.y = min.y.min(side.point1.y);
+            max.x = max.x.max(side.point1.x);
+            max.y = max.y.max(side.point1.y);
+        }
+        let usable_width = viewport.width as f64 * (1.0 - 2.0 * MAP_MARGIN);
+        let usable_height = viewport.height as f64 * (1.0 - 2.0 * MAP_MARGIN);
+        let scale = (usable_width / (max.x - min.x)).min(usable_height / (max.y - min.y));
+
+        // center the map in the viewport
+        let offset_x = viewport.x as f64 + (viewport.width as f64 - (max.x - min.x) * scale) / 2.0
+            - min.x * scale;
+        let offset_y = viewport.y as f64 + (viewport.height as f64 - (max.y - min.y) * scale) / 2.0
+            - min.y * scale;
+        MapTransform {
+            scale,
+            offset_x,
+            offset_y,
+        }
+    }
+
+    fn to_screen(&self, point: Point) -> (isize, isize) {
+        (
+            (point.x * self.scale + self.offset_x) as isize,
+            (point.y * self.scale + self.offset_y) as isize,
+        )
+    }
+
+    fn to_world(&self, x: isize, y: isize) -> Point {
+        Point {
+            x: (x as f64 + 0.5 - self.offset_x) / self.scale,
+            y: (y as f64 + 0.5 - self.offset_y) / self.scale,
+        }
+    }
+}
+
+pub fn draw_topdown(
+    buffer: &mut [u32],
+    renderer_data: &RendererData,
+    game: &Game,
+    viewport: Viewport,
+) {
+    if viewport.width == 0 || viewport.height == 0 {
+        return;
+    }
+    let metadata = &game.map.metadata;
+    for y in viewport.y..viewport.y + viewport.height {
+        for x in viewport.x..viewport.x + viewport.width {
+            buffer[y * viewport.stride + x] = metadata.background_color;
+        }
+    }
+
+    let transform = MapTransform::fit(&game.map.border, &viewport);
+
+    for block in &game.map.blocks {
+        fill_shape(
+            buffer,
+            &transform,
+            &viewport,
+            block,
+            metadata.block_default_color,
+        );
+    }
+    draw_outline(
+        buffer,
+        &transform,
+        &viewport,
+        &game.map.border,
+        BORDER_COLOR,
+    );
+    for wall in &game.map.walls {
+        draw_outline(
+            buffer,
+            &transform,
+            &viewport,
+            wall,
+            metadata.wall_default_color,
+        );
+    }
+    if let Some(exit) = &game.map.exit {
+        draw_outline(buffer, &transform, &viewport, exit, EXIT_COLOR);
+    }
+
+    let player_position = Point {
+        x: game.player.position_x,
+        y: game.player.position_y,
+    };
+    let (player_x, player_y) = transform.to_screen(player_position);
+
+    if renderer_data.show_rays {
+        // the same rays the camera view casts, thinned out
+        for x in (0..renderer_data.screen_width).step_by(RAY_SPACING) {
+            let pixel_distance_from_screen_middle =
+                x as f64 - renderer_data.screen_width_as_f64 / 2.0;
+            let ray_angle = game.player.view_angle
+                + (pixel_distance_from_screen_middle / renderer_data.projection_plane_distance)
+                    .atan();
+            let mut closest_hit = None;
+            game.map.side_grid.cast_ray(
+                game.map.shapes(),
+                player_position,
+                ray_angle,
+                f64::INFINITY,
+                |side_ref, hit| {
+                    if side_ref.shape_type == ShapeType::Sector {
+                        return true;
+                    }
+                    closest_hit = Some(hit);
+                    false
+                },
+            );
+            if let Some(hit) = closest_hit {
+                let (hit_x, hit_y) = transform.to_screen(hit.position);
+                draw_line(
+                    buffer, &viewport, player_x, player_y, hit_x, hit_y, RAY_COLOR,
+                );
+            }
+        }
+    }
+
+    for enemy in &game.enemies {
+        let color = match enemy.state {
+            EnemyState::Dead => DEAD_ENEMY_COLOR,
+            _ => ENEMY_COLOR,
+        };
+        let (enemy_x, enemy_y) = transform.to_screen(enemy.position);
+        draw_square(buffer, &viewport, enemy_x, enemy_y, color);
+    }
+
+    //draw player as a small square with a line in the direction he is looking
+    let direction_end = Point {
+        x: player_position.x + game.player.view_angle.cos() * PLAYER_DIRECTION_LENGTH,
+        y: player_position.y + game.player.view_angle.sin() * PLAYER_DIRECTION_LENGTH,
+    };
+    let (direction_x, direction_y) = transform.to_screen(direction_end);
+    draw_line(
+        buffer,
+        &viewport,
+        player_x,
+        player_y,
+        direction_x,
+        direction_y,
+        PLAYER_DIRECTION_COLOR,
+    );
+    draw_square(buffer, &viewport, player_x, player_y, PLAYER_COLOR);
+
+    // frame so the minimap stands out from the camera view behind it
+    let right = (viewport.x + viewport.width - 1) as isize;
+    let bottom = (viewport.y + viewport.height - 1) as isize;
+    let (left, top) = (viewport.x as isize, viewport.y as isize);
+    draw_line(buffer, &viewport, left, top, right, top, FRAME_COLOR);
+    draw_line(buffer, &viewport, right, top, right, bottom, FRAME_COLOR);
+    draw_line(buffer, &viewport, right, bottom, left, bottom, FRAME_COLOR);
+    draw_line(buffer, &viewport, left, bottom, left, top, FRAME_COLOR);
+}
+
+fn draw_outline(
+    buffer: &mut [u32],
+    transform: &MapTransform,
+    viewport: &Viewport,
+    shape: &Shape,
+    color: u32,
+) {
+    for side in &shape.sides {
+        let (x0, y0) = transform.to_screen(side.point1);
+        let (x1, y1) = transform.to_screen(side.point2);
+        draw_line(buffer, viewport, x0, y0, x1, y1, color);
+    }
+}
+
+// tests every pixel inside the bounding box of the shape; fine for the handful of blocks a map has
+fn fill_shape(
+    buffer: &mut [u32],
+    transform: &MapTransform,
+    viewport: &Viewport,
+    shape: &Shape,
+    color: u32,
+) {
+    let (mut min_x, mut min_y) = (isize::MAX, isize::MAX);
+    let (mut max_x, mut max_y) = (isize::MIN, isize::MIN);
+    for side in &shape.sides {
+        let (x, y) = transform.to_screen(side.point1);
+        min_x = min_x.min(x);
+        min_y = min_y.min(y);
+        max_x = max_x.max(x);
+        max_y = max_y.max(y);
+    }
+    for y in min_y..=max_y {
+        for x in min_x..=max_x {
+            if shape.contains(transform.to_world(x, y)) {
+                put_pixel(buffer, viewport, x, y, color);
+            }
+        }
+    }
+}
+
+// 3x3 pixels around the point
+fn draw_square(buffer: &mut [u32], viewport: &Viewport, x: isize, y: isize, color: u32) {
+    for dx in -1..=1 {
+        for dy in -1..=1 {
+            put_pixel(buffer, viewport, x + dx, y + dy, color);
+        }
+    }
+}
+
+fn put_pixel(buffer: &mut [u32], viewport: &Viewport, x: isize, y: isize, color: u32) {
+    if viewport.contains(x, y) {
+        buffer[y as usize * viewport.stride + x as usize] = color;
+    }
+}
+
+// Bresenham; pixels outside of the viewport are skipped
+fn draw_line(
+    buffer: &mut [u32],
+    viewport: &Viewport,
+    x0: isize,
+    y0: isize,
+    x1: isize,
+    y1: isize,
+    color: u32,
+) {
+    let (mut x, mut y) = (x0, y0);
+    let dx = (x1 - x0).abs();
+    let sx = if x0 < x1 { 1 } else { -1 };
+    let dy = -(y1 - y0).abs();
+    let sy = if y0 < y1 { 1 } else { -1 };
+    let mut err = dx + dy;
+
+    loop {
+        put_pixel(buffer, viewport, x, y, color);
+
+        if x == x1 && y == y1 {
+            break;
+        }
+
+        let e2 = 2 * err;
+        if e2 >= dy {
+            err += dy;
+            x += sx;
+        }
+        if e2 <= dx {
+            err += dx;
+            y += sy;
+        }
+    }
+}
@@ -0,0 +1,365 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::input::Action;
+use crate::game::player::MOUSE_SENSITIVITY;
+use crate::window_input::{RESERVED_KEYS, Trigger};
+
+const DEFAULT_BINDINGS: &[(Action, &[&str])] = &[
+    (Action::MoveForward, &["W"]),
+    (Action::MoveBackward, &["S"]),
+    (Action::StrafeLeft, &["A"]),
+    (Action::StrafeRight, &["D"]),
+    (Action::TurnLeft, &["Q"]),
+    (Action::TurnRight, &["E"]),
+    (Action::LookUp, &["Up"]),
+    (Action::LookDown, &["Down"]),
+    (Action::Jump, &["Space"]),
+    (Action::FlyDown, &["LeftShift"]),
+    (Action::Fire, &["LeftCtrl", "MouseLeft"]),
+    (Action::Use, &["F"]),
+    (Action::SelectWeapon1, &["Key1"]),
+    (Action::SelectWeapon2, &["Key2"]),
+];
+const MAX_MOUSE_SENSITIVITY: f64 = 0.1; // radians per pixel, beyond that a single pixel turns too far
+pub const MIN_FOV: f64 = 30.0; // degrees
+pub const MAX_FOV: f64 = 150.0; // the projection gets too distorted beyond that
+const MIN_RESOLUTION: (usize, usize) = (160, 90);
+const MAX_RESOLUTION: (usize, usize) = (7680, 4320);
+const MAX_TARGET_FPS: usize = 1000;
+const MIN_RENDER_SCALE: f64 = 0.1;
+const USAGE: &str =
+    "usage: game [--resolution <width>x<height>] [--fov <degrees>] [--render-scale <scale>]
+       game screenshot --help";
+
+// everything the player can change without touching the code; every field can be left out of the settings file
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    // key or mouse button names per action, e.g. `Fire: ["LeftCtrl", "MouseLeft"]`;
+    // actions left out keep their default bindings, an empty list unbinds the action
+    pub bindings: BTreeMap<Action, Vec<String>>,
+    pub mouse_sensitivity: f64, // radians per pixel the mouse moves
+    pub invert_y: bool,
+    pub fov: f64, // horizontal, in degrees
+    pub resolution: (usize, usize),
+    pub render_scale: f64, // the camera view is rendered this much smaller than the window and scaled up
+    pub target_fps: usize, // 0 doesn't limit the frame rate
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            bindings: BTreeMap::new(),
+            mouse_sensitivity: MOUSE_SENSITIVITY,
+            invert_y: false,
+            fov: 90.0,
+            resolution: (800, 450),
+            render_scale: 1.0,
+            target_fps: 60,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum SettingsError {
+    Io {
+        path: PathBuf,
+        source: io::Error,
+    },
+    Parse {
+        path: PathBuf,
+        source: ron::error::SpannedError,
+    },
+    Invalid {
+        path: PathBuf,
+        reason: String,
+    },
+}
+
+impl fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SettingsError::Io { path, source } => {
+                write!(f, "could not read settings {}: {source}", path.display())
+            }
+            SettingsError::Parse { path, source } => {
+                write!(f, "could not parse settings {}: {source}", path.display())
+            }
+            SettingsError::Invalid { path, reason } => {
+                write!(f, "invalid settings {}: {reason}", path.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for SettingsError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SettingsError::Io { source, .. } => Some(source),
+            SettingsError::Parse { source, .. } => Some(source),
+            SettingsError::Invalid { .. } => None,
+        }
+    }
+}
+
+impl Settings {
+    // a missing file gives the defaults, a broken one an error
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, SettingsError> {
+        let path = path.as_ref();
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(source) => {
+                return Err(SettingsError::Io {
+                    path: path.to_path_buf(),
+                    source,
+                });
+            }
+        };
+        let settings: Self = ron::from_str(&contents).map_err(|source| SettingsError::Parse {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        settings
+            .validate()
+            .map_err(|reason| SettingsError::Invalid {
+                path: path.to_path_buf(),
+                reason,
+            })?;
+        Ok(settings)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        self.triggers()?;
+        if !self.mouse_sensitivity.is_finite()
+            || self.mouse_sensitivity <= 0.0
+            || self.mouse_sensitivity > MAX_MOUSE_SENSITIVITY
+        {
+            return Err(format!(
+                "mouse_sensitivity must be above 0 and at most {MAX_MOUSE_SENSITIVITY}, got {}",
+                self.mouse_sensitivity
+            ));
+        }
+        validate_view(self.resolution, self.fov, self.render_scale)?;
+        if self.target_fps > MAX_TARGET_FPS {
+            return Err(format!(
+                "target_fps must be at most {MAX_TARGET_FPS} (0 for no limit), got {}",
+                self.target_fps
+            ));
+        }
+        Ok(())
+    }
+
+    // `--resolution <width>x<height>`, `--fov <degrees>` and `--render-scale <scale>` from the command line
+    // win over the file
+    pub fn override_from_args(&mut self, args: &[String]) -> Result<(), String> {
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| format!("{arg} needs a value\n\n{USAGE}"))
+            };
+            match arg.as_str() {
+                "--resolution" => {
+                    let value = value()?;
+                    self.resolution = parse_resolution(value).ok_or_else(|| {
+                        format!("--resolution expects <width>x<height>, got {value:?}")
+                    })?
+                }
+                "--fov" => {
+                    let value = value()?;
+                    self.fov = value
+                        .trim()
+                        .parse()
+                        .map_err(|_| format!("--fov expects a number, got {value:?}"))?
+                }
+                "--render-scale" => {
+                    let value = value()?;
+                    self.render_scale = value
+                        .trim()
+                        .parse()
+                        .map_err(|_| format!("--render-scale expects a number, got {value:?}"))?
+                }
+                _ => return Err(format!("unknown option {arg:?}\n\n{USAGE}")),
+            }
+        }
+        validate_view(self.resolution, self.fov, self.render_scale)
+    }
+
+    // the bindings from the file on top of the defaults
+    pub fn triggers(&self) -> Result<Vec<(Trigger, Action)>, String> {
+        let mut triggers = Vec::new();
+        for action in Action::ALL {
+            for name in self.binding_names(action) {
+                let trigger = Trigger::parse(name).ok_or_else(|| {
+                    format!("bindings: unknown key or mouse button {name:?} for {action:?}")
+                })?;
+                if let Trigger::Key(key) = trigger
+                    && RESERVED_KEYS.contains(&key)
+                {
+                    return Err(format!(
+                        "bindings: {name:?} for {action:?} is taken by the game's own toggles"
+                    ));
+                }
+                triggers.push((trigger, action));
+            }
+        }
+        Ok(triggers)
+    }
+
+    pub fn binding_names(&self, action: Action) -> Vec<&str> {
+        match self.bindings.get(&action) {
+            Some(names) => names.iter().map(String::as_str).collect(),
+            None => DEFAULT_BINDINGS
+                .iter()
+                .find(|(default_action, _)| *default_action == action)
+                .map_or_else(Vec::new, |(_, names)| names.to_vec()),
+        }
+    }
+}
+
+// 1280x720 -> (1280, 720)
+pub fn parse_resolution(value: &str) -> Option<(usize, usize)> {
+    let (width, height) = value.trim().split_once('x')?;
+    Some((width.parse().ok()?, height.parse().ok()?))
+}
+
+// the nearest resolution validate_view accepts; the window can be resized to anything
+pub fn clamp_resolution((width, height): (usize, usize)) -> (usize, usize) {
+    (
+        width.clamp(MIN_RESOLUTION.0, MAX_RESOLUTION.0),
+        height.clamp(MIN_RESOLUTION.1, MAX_RESOLUTION.1),
+    )
+}
+
+// also checks the command line of the game and of the screenshots
+pub fn validate_view(
+    resolution: (usize, usize),
+    fov: f64,
+    render_scale: f64,
+) -> Result<(), String> {
+    if !(MIN_FOV..=MAX_FOV).contains(&fov) {
+        return Err(format!(
+            "fov must be between {MIN_FOV} and {MAX_FOV} degrees, got {fov}"
+        ));
+    }
+    let (width, height) = resolution;
+    if !(MIN_RESOLUTION.0..=MAX_RESOLUTION.0).contains(&width)
+        || !(MIN_RESOLUTION.1..=MAX_RESOLUTION.1).contains(&height)
+    {
+        return Err(format!(
+            "resolution must be between {}x{} and {}x{}, got {width}x{height}",
+            MIN_RESOLUTION.0, MIN_RESOLUTION.1, MAX_RESOLUTION.0, MAX_RESOLUTION.1
+        ));
+    }
+    if !(MIN_RENDER_SCALE..=1.0).contains(&render_scale) {
+        return Err(format!(
+            "render_scale must be between {MIN_RENDER_SCALE} and 1, got {render_scale}"
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn parses_resolutions() {
+        assert_eq!(parse_resolution("1280x720"), Some((1280, 720)));
+        assert_eq!(parse_resolution(" 800x450 "), Some((800, 450)));
+        assert_eq!(parse_resolution("1280*720"), None);
+        assert_eq!(parse_resolution("1280x"), None);
+        assert_eq!(parse_resolution("-1x720"), None);
+    }
+
+    #[test]
+    fn command_line_overrides_the_file() {
+        let mut settings = Settings::default();
+        settings
+            .override_from_args(&args(&[
+                "--resolution",
+                "1280x720",
+                "--fov",
+                "100",
+                "--render-scale",
+                "0.5",
+            ]))
+            .unwrap();
+        assert_eq!(settings.resolution, (1280, 720));
+        assert_eq!(settings.fov, 100.0);
+        assert_eq!(settings.render_scale, 0.5);
+
+        for bad in [
+            &["--fov"][..],
+            &["--fov", "wide"],
+            &["--fov", "200"],
+            &["--resolution", "10x10"],
+            &["--render-scale", "2"],
+            &["--fullscreen"],
+        ] {
+            assert!(
+                Settings::default().override_from_args(&args(bad)).is_err(),
+                "{bad:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn validates_settings() {
+        assert_eq!(Settings::default().validate(), Ok(()));
+        Settings::load("settings.ron").expect("the settings file of the repository is valid");
+
+        let mut rebound = Settings::default();
+        rebound
+            .bindings
+            .insert(Action::Jump, vec![String::from("MouseRight")]);
+        assert_eq!(rebound.validate(), Ok(()));
+        assert_eq!(rebound.binding_names(Action::Jump), vec!["MouseRight"]);
+        assert_eq!(rebound.binding_names(Action::Use), vec!["F"]);
+
+        for name in ["Unknown", "R", "Tab", "F3", "LeftBracket"] {
+            let mut settings = Settings::default();
+            settings
+                .bindings
+                .insert(Action::Use, vec![String::from(name)]);
+            assert!(settings.validate().is_err(), "{name}");
+        }
+
+        let invalid = [
+            Settings {
+                mouse_sensitivity: 0.0,
+                ..Settings::default()
+            },
+            Settings {
+                fov: 10.0,
+                ..Settings::default()
+            },
+            Settings {
+                resolution: (100, 50),
+                ..Settings::default()
+            },
+            Settings {
+                render_scale: 0.0,
+                ..Settings::default()
+            },
+            Settings {
+                target_fps: MAX_TARGET_FPS + 1,
+                ..Settings::default()
+            },
+        ];
+        for settings in invalid {
+            assert!(settings.validate().is_err(), "{settings:?}");
+        }
+    }
+}
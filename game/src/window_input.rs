@@ -0,0 +1,132 @@
+use crate::game::input::{Action, InputState};
+use minifb::{Key, KeyRepeat, MouseButton, MouseMode, Window};
+
+// a key or mouse button that can be bound to an action
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Trigger {
+    Key(Key),
+    Mouse(MouseButton),
+}
+
+// key names are the names of the minifb keys, so they stay easy to look up
+macro_rules! key_names {
+    ($($key:ident),* $(,)?) => {
+        &[$((stringify!($key), Key::$key)),*]
+    };
+}
+
+#[rustfmt::skip]
+const KEY_NAMES: &[(&str, Key)] = key_names![
+    A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z,
+    Key0, Key1, Key2, Key3, Key4, Key5, Key6, Key7, Key8, Key9,
+    F1, F2, F3, F4, F5, F6, F7, F8, F9, F10, F11, F12,
+    Up, Down, Left, Right, Space, Tab, Enter, Backspace, Insert, Delete, Home, End, PageUp, PageDown,
+    LeftShift, RightShift, LeftCtrl, RightCtrl, LeftAlt, RightAlt,
+    NumPad0, NumPad1, NumPad2, NumPad3, NumPad4, NumPad5, NumPad6, NumPad7, NumPad8, NumPad9,
+    Comma, Period, Slash, Semicolon, Apostrophe, LeftBracket, RightBracket, Backslash, Minus, Equal,
+];
+// keys App handles itself while the game runs (view toggles, fov, pause); binding them would do both
+pub const RESERVED_KEYS: &[Key] = &[
+    Key::Escape,
+    Key::Tab,
+    Key::M,
+    Key::R,
+    Key::F3,
+    Key::N,
+    Key::I,
+    Key::T,
+    Key::LeftBracket,
+    Key::RightBracket,
+];
+const MOUSE_NAMES: [(&str, MouseButton); 3] = [
+    ("MouseLeft", MouseButton::Left),
+    ("MouseRight", MouseButton::Right),
+    ("MouseMiddle", MouseButton::Middle),
+];
+
+impl Trigger {
+    // the name used for it in the settings file, e.g. "W", "Space", "MouseLeft"
+    pub fn parse(name: &str) -> Option<Self> {
+        let key = KEY_NAMES
+            .iter()
+            .find(|(key_name, _)| *key_name == name)
+            .map(|&(_, key)| Trigger::Key(key));
+        key.or_else(|| {
+            MOUSE_NAMES
+                .iter()
+                .find(|(button_name, _)| *button_name == name)
+                .map(|&(_, button)| Trigger::Mouse(button))
+        })
+    }
+}
+
+// turns the keys and mouse of a minifb window into the InputState the game understands
+pub struct WindowInput {
+    bindings: Vec<(Trigger, Action)>, // several triggers may share an action
+    last_mouse: Option<(f32, f32)>,   // None until the mouse was seen over the window
+    last_buttons: [bool; MOUSE_NAMES.len()], // which mouse buttons were down in the last read, in MOUSE_NAMES order
+}
+
+impl WindowInput {
+    pub fn new(bindings: Vec<(Trigger, Action)>) -> Self {
+        Self {
+            bindings,
+            last_mouse: None,
+            last_buttons: [false; MOUSE_NAMES.len()],
+        }
+    }
+
+    pub fn set_bindings(&mut self, bindings: Vec<(Trigger, Action)>) {
+        self.bindings = bindings;
+    }
+
+    // call once per frame, before the game updates
+    pub fn read(&mut self, window: &Window) -> InputState {
+        let mut input = InputState::default();
+        // minifb only tells whether a button is down, a press is a button that was up in the last read
+        let buttons = mouse_buttons(window);
+        for &(trigger, action) in &self.bindings {
+            match trigger {
+                Trigger::Key(key) if window.is_key_pressed(key, KeyRepeat::No) => {
+                    input.press(action)
+                }
+                Trigger::Key(key) if window.is_key_down(key) => input.hold(action),
+                Trigger::Mouse(button) => {
+                    let index = mouse_index(button);
+                    if buttons[index] && !self.last_buttons[index] {
+                        input.press(action);
+                    } else if buttons[index] {
+                        input.hold(action);
+                    }
+                }
+                _ => {}
+            }
+        }
+        self.last_buttons = buttons;
+
+        if let Some((x, y)) = window.get_mouse_pos(MouseMode::Pass) {
+            if let Some((last_x, last_y)) = self.last_mouse {
+                input.mouse_delta = ((x - last_x) as f64, (y - last_y) as f64);
+            }
+            self.last_mouse = Some((x, y));
+        }
+        input
+    }
+
+    // forgets where the mouse was, so moving it while the game did not read input doesn't turn the player
+    pub fn reset_mouse(&mut self, window: &Window) {
+        self.last_mouse = window.get_mouse_pos(MouseMode::Pass);
+        self.last_buttons = mouse_buttons(window);
+    }
+}
+
+fn mouse_buttons(window: &Window) -> [bool; MOUSE_NAMES.len()] {
+    MOUSE_NAMES.map(|(_, button)| window.get_mouse_down(button))
+}
+
+fn mouse_index(button: MouseButton) -> usize {
+    MOUSE_NAMES
+        .iter()
+        .position(|&(_, named)| named == button)
+        .expect("every mouse button that can be bound is named")
+}